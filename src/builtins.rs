@@ -0,0 +1,512 @@
+/// A function provided by the language rather than declared in the script.
+pub struct Signature {
+    pub name: &'static str,
+    pub arity: usize,
+    /// Whether calls with constant arguments may be folded at compile time.
+    pub pure: bool,
+    /// Whether the call also takes any number of arguments past `arity`.
+    pub variadic: bool,
+}
+
+const fn signature(name: &'static str, arity: usize, pure: bool) -> Signature {
+    Signature {
+        name,
+        arity,
+        pure,
+        variadic: false,
+    }
+}
+
+const fn variadic(name: &'static str, arity: usize) -> Signature {
+    Signature {
+        name,
+        arity,
+        pure: false,
+        variadic: true,
+    }
+}
+
+/// Mindustry's math functions, each compiled to the `op` of the same name.
+pub const MATH: &[Signature] = &[
+    signature("max", 2, true),
+    signature("min", 2, true),
+    signature("angle", 2, true),
+    signature("angleDiff", 2, true),
+    signature("len", 2, true),
+    signature("noise", 2, false),
+    signature("abs", 1, true),
+    signature("log", 1, true),
+    signature("log10", 1, true),
+    signature("floor", 1, true),
+    signature("ceil", 1, true),
+    signature("sqrt", 1, true),
+    signature("rand", 1, false),
+    signature("sin", 1, true),
+    signature("cos", 1, true),
+    signature("tan", 1, true),
+    signature("asin", 1, true),
+    signature("acos", 1, true),
+    signature("atan", 1, true),
+];
+
+/// Conversions between plain numbers and angles. Degrees only exist at
+/// compile time, so both compile to nothing.
+pub const CONVERSIONS: &[Signature] = &[signature("num", 1, true), signature("deg", 1, true)];
+
+/// Colour constructors and blending. `rgb`/`rgba` compile to `packcolor`.
+pub const COLOUR: &[Signature] = &[
+    signature("rgb", 3, true),
+    signature("rgba", 4, true),
+    signature("blend", 3, true),
+];
+
+/// Properties of a colour, in `packcolor` order. On V8 they compile to
+/// `unpackcolor`.
+pub const COLOUR_COMPONENTS: &[&str] = &["r", "g", "b", "a"];
+
+/// Processor links. `getlink` compiles to the instruction of the same name.
+pub const LINKS: &[Signature] = &[signature("getlink", 1, false)];
+
+/// Unit binding and sensing. `ubind` selects the unit the others act on,
+/// which compile to `uradar` and `ulocate`.
+pub const UNIT: &[Signature] = &[
+    signature("ubind", 1, false),
+    signature("uradar", 5, false),
+    // Returns whether anything was found, its position and the building.
+    // Takes the kind, then the arguments that kind uses.
+    variadic("ulocate", 1),
+];
+
+/// Commands for the bound unit, called as methods such as
+/// `@unit.move(x, y)` and compiled to `ucontrol`.
+pub const UNIT_CONTROLS: &[Signature] = &[
+    signature("move", 2, false),
+    signature("approach", 3, false),
+    signature("mine", 2, false),
+    signature("itemDrop", 2, false),
+    signature("itemTake", 3, false),
+    signature("boost", 1, false),
+    signature("flag", 1, false),
+    signature("within", 3, false),
+    // Returns the block type, building and floor.
+    signature("getBlock", 2, false),
+];
+
+pub fn unit_control(name: &str) -> Option<&'static Signature> {
+    UNIT_CONTROLS
+        .iter()
+        .find(|signature| signature.name == name)
+}
+
+/// Commands buffered by `draw` until `drawflush` sends them to a display,
+/// called as `draw.line(x, y, x2, y2)`.
+pub const DRAW: &[Signature] = &[
+    signature("clear", 3, false),
+    signature("color", 4, false),
+    signature("col", 1, false),
+    signature("stroke", 1, false),
+    signature("line", 4, false),
+    signature("rect", 4, false),
+    signature("lineRect", 4, false),
+    signature("poly", 5, false),
+    signature("linePoly", 5, false),
+    signature("triangle", 6, false),
+    signature("image", 5, false),
+    signature("print", 3, false),
+];
+
+pub fn draw(name: &str) -> Option<&'static Signature> {
+    DRAW.iter().find(|signature| signature.name == name)
+}
+
+/// Text output. `print` appends its arguments to the text buffer, which
+/// `printflush` sends to a message block.
+pub const TEXT: &[Signature] = &[variadic("print", 1)];
+
+/// Instructions sending buffered output to a linked building.
+pub const FLUSHES: &[Signature] = &[
+    signature("drawflush", 1, false),
+    signature("printflush", 1, false),
+];
+
+#[rustfmt::skip]
+pub const TEXT_ALIGNS: &[&str] = &[
+    "center", "top", "bottom", "left", "right", "topLeft", "topRight", "bottomLeft",
+    "bottomRight",
+];
+
+#[rustfmt::skip]
+pub const RADAR_TARGETS: &[&str] = &[
+    "any", "enemy", "ally", "player", "attacker", "flying", "boss", "ground",
+];
+
+pub const RADAR_SORTS: &[&str] = &["distance", "health", "shield", "armor", "maxHealth"];
+
+pub const LOCATE_KINDS: &[&str] = &["building", "ore", "spawn", "damaged"];
+
+#[rustfmt::skip]
+pub const BUILDING_GROUPS: &[&str] = &[
+    "core", "storage", "generator", "turret", "factory", "repair", "battery", "reactor", "drill",
+    "shield",
+];
+
+/// The words allowed as argument `index` of a call to `name` with `count`
+/// arguments, for instruction arguments that are written as a fixed word
+/// rather than a value.
+pub fn keywords(name: &str, index: usize, count: usize) -> Option<&'static [&'static str]> {
+    match (name, index) {
+        ("uradar", 0..=2) => Some(RADAR_TARGETS),
+        ("uradar", 3) => Some(RADAR_SORTS),
+        ("ulocate", 0) => Some(LOCATE_KINDS),
+        // Only the `building` kind takes a group, the `ore` kind names an
+        // ore instead.
+        ("ulocate", 1) if count > 2 => Some(BUILDING_GROUPS),
+        _ => None,
+    }
+}
+
+pub fn lookup(name: &str) -> Option<&'static Signature> {
+    MATH.iter()
+        .chain(CONVERSIONS)
+        .chain(COLOUR)
+        .chain(LINKS)
+        .chain(UNIT)
+        .chain(TEXT)
+        .chain(FLUSHES)
+        .find(|signature| signature.name == name)
+}
+
+/// Building controls, called as methods such as `turret.shoot(x, y, true)`
+/// and compiled to `control`.
+pub const CONTROLS: &[Signature] = &[
+    signature("enabled", 1, false),
+    signature("shoot", 3, false),
+    signature("shootp", 2, false),
+    signature("config", 1, false),
+    signature("color", 1, false),
+];
+
+pub fn control(name: &str) -> Option<&'static Signature> {
+    CONTROLS.iter().find(|signature| signature.name == name)
+}
+
+/// Named colours usable as `%[name]`, matching the game's palette.
+pub const NAMED_COLOURS: &[(&str, u32)] = &[
+    ("white", 0xffffffff),
+    ("lightgray", 0xbfbfbfff),
+    ("gray", 0x7f7f7fff),
+    ("darkgray", 0x3f3f3fff),
+    ("black", 0x000000ff),
+    ("clear", 0x00000000),
+    ("blue", 0x0000ffff),
+    ("navy", 0x00007fff),
+    ("royal", 0x4169e1ff),
+    ("slate", 0x708090ff),
+    ("sky", 0x87ceebff),
+    ("cyan", 0x00ffffff),
+    ("teal", 0x007f7fff),
+    ("green", 0x00ff00ff),
+    ("acid", 0x7fff00ff),
+    ("lime", 0x32cd32ff),
+    ("forest", 0x228b22ff),
+    ("olive", 0x6b8e23ff),
+    ("yellow", 0xffff00ff),
+    ("gold", 0xffd700ff),
+    ("goldenrod", 0xdaa520ff),
+    ("orange", 0xffa500ff),
+    ("brown", 0x8b4513ff),
+    ("tan", 0xd2b48cff),
+    ("brick", 0xb22222ff),
+    ("red", 0xff0000ff),
+    ("scarlet", 0xff341cff),
+    ("crimson", 0xdc143cff),
+    ("coral", 0xff7f50ff),
+    ("salmon", 0xfa8072ff),
+    ("pink", 0xff69b4ff),
+    ("magenta", 0xff00ffff),
+    ("purple", 0xa020f0ff),
+    ("violet", 0xee82eeff),
+    ("maroon", 0xb03060ff),
+];
+
+pub fn named_colour(name: &str) -> Option<u32> {
+    NAMED_COLOURS
+        .iter()
+        .find(|(colour, _)| colour.eq_ignore_ascii_case(name))
+        .map(|(_, packed)| *packed)
+}
+
+/// Built-in variables whose value is only known while the processor runs.
+#[rustfmt::skip]
+pub const VARIABLES: &[&str] = &[
+    "@this", "@thisx", "@thisy", "@ipt", "@counter", "@links", "@unit", "@time", "@tick",
+    "@second", "@minute", "@waveNumber", "@waveTime", "@mapw", "@maph", "@server", "@client",
+    "@clientLocale", "@clientUnit", "@clientName", "@clientTeam", "@clientMobile",
+    "@blockCount", "@unitCount", "@itemCount", "@liquidCount",
+];
+
+/// Built-in variables that always hold a number.
+#[rustfmt::skip]
+pub const NUMERIC_VARIABLES: &[&str] = &[
+    "@thisx", "@thisy", "@ipt", "@counter", "@links", "@time", "@tick", "@second", "@minute",
+    "@waveNumber", "@waveTime", "@mapw", "@maph", "@server", "@client", "@clientMobile",
+    "@blockCount", "@unitCount", "@itemCount", "@liquidCount",
+];
+
+/// Built-in variables with a fixed numeric value.
+pub const MATH_CONSTANTS: &[(&str, f32)] = &[
+    ("@pi", std::f32::consts::PI),
+    ("@e", std::f32::consts::E),
+    ("@degToRad", std::f32::consts::PI / 180.),
+    ("@radToDeg", 180. / std::f32::consts::PI),
+];
+
+/// A property `sensor` can read from a building or unit.
+pub struct Sensor {
+    pub name: &'static str,
+    /// The `Object` type of the value read, or `Team`/`Unit` for in-game
+    /// references.
+    pub dtype: &'static str,
+}
+
+const fn sensor(name: &'static str, dtype: &'static str) -> Sensor {
+    Sensor { name, dtype }
+}
+
+/// Properties that `sensor` can read from a building or unit.
+pub const SENSORS: &[Sensor] = &[
+    sensor("@totalItems", "Number"),
+    sensor("@firstItem", "Content"),
+    sensor("@totalLiquids", "Number"),
+    sensor("@totalPower", "Number"),
+    sensor("@itemCapacity", "Number"),
+    sensor("@liquidCapacity", "Number"),
+    sensor("@powerCapacity", "Number"),
+    sensor("@powerNetStored", "Number"),
+    sensor("@powerNetCapacity", "Number"),
+    sensor("@powerNetIn", "Number"),
+    sensor("@powerNetOut", "Number"),
+    sensor("@ammo", "Number"),
+    sensor("@ammoCapacity", "Number"),
+    sensor("@health", "Number"),
+    sensor("@maxHealth", "Number"),
+    sensor("@heat", "Number"),
+    sensor("@shield", "Number"),
+    sensor("@armor", "Number"),
+    sensor("@efficiency", "Number"),
+    sensor("@progress", "Number"),
+    sensor("@timescale", "Number"),
+    sensor("@rotation", "Number"),
+    sensor("@x", "Number"),
+    sensor("@y", "Number"),
+    sensor("@velocityX", "Number"),
+    sensor("@velocityY", "Number"),
+    sensor("@shootX", "Number"),
+    sensor("@shootY", "Number"),
+    sensor("@cameraX", "Number"),
+    sensor("@cameraY", "Number"),
+    sensor("@cameraWidth", "Number"),
+    sensor("@cameraHeight", "Number"),
+    sensor("@size", "Number"),
+    sensor("@solid", "Boolean"),
+    sensor("@dead", "Boolean"),
+    sensor("@range", "Number"),
+    sensor("@shooting", "Boolean"),
+    sensor("@boosting", "Boolean"),
+    sensor("@mineX", "Number"),
+    sensor("@mineY", "Number"),
+    sensor("@mining", "Boolean"),
+    sensor("@speed", "Number"),
+    sensor("@team", "Team"),
+    sensor("@type", "Content"),
+    sensor("@flag", "Number"),
+    sensor("@controlled", "Number"),
+    sensor("@controller", "Unit"),
+    sensor("@name", "String"),
+    sensor("@payloadCount", "Number"),
+    sensor("@payloadType", "Content"),
+    sensor("@id", "Number"),
+    sensor("@enabled", "Boolean"),
+    sensor("@config", "Content"),
+    sensor("@color", "Colour"),
+];
+
+/// The property read by `object.name`: a sensor, or the amount of an item
+/// or liquid.
+pub fn sensor_property(name: &str) -> Option<&'static Sensor> {
+    const AMOUNT: Sensor = sensor("", "Number");
+    let name = format!("@{name}");
+    let amount = ITEMS.contains(&name.as_str()) || LIQUIDS.contains(&name.as_str());
+    SENSORS
+        .iter()
+        .find(|sensor| sensor.name == name)
+        .or_else(|| amount.then_some(&AMOUNT))
+}
+
+pub const TEAMS: &[&str] = &[
+    "@derelict",
+    "@sharded",
+    "@crux",
+    "@malis",
+    "@green",
+    "@blue",
+];
+
+#[rustfmt::skip]
+pub const ITEMS: &[&str] = &[
+    "@copper", "@lead", "@metaglass", "@graphite", "@sand", "@coal", "@titanium", "@thorium",
+    "@scrap", "@silicon", "@plastanium", "@phase-fabric", "@surge-alloy", "@spore-pod",
+    "@blast-compound", "@pyratite", "@beryllium", "@tungsten", "@oxide", "@carbide",
+    "@fissile-matter", "@dormant-cyst",
+];
+
+#[rustfmt::skip]
+pub const LIQUIDS: &[&str] = &[
+    "@water", "@slag", "@oil", "@cryofluid", "@neoplasm", "@arkycite", "@gallium", "@ozone",
+    "@hydrogen", "@nitrogen", "@cyanogen",
+];
+
+#[rustfmt::skip]
+pub const UNITS: &[&str] = &[
+    "@dagger", "@mace", "@fortress", "@scepter", "@reign", "@nova", "@pulsar", "@quasar",
+    "@vela", "@corvus", "@crawler", "@atrax", "@spiroct", "@arkyid", "@toxopid", "@flare",
+    "@horizon", "@zenith", "@antumbra", "@eclipse", "@mono", "@poly", "@mega", "@quad", "@oct",
+    "@risso", "@minke", "@bryde", "@sei", "@omura", "@retusa", "@oxynoe", "@cyerce", "@aegires",
+    "@navanax", "@alpha", "@beta", "@gamma", "@stell", "@locus", "@precept", "@vanquish",
+    "@conquer", "@merui", "@cleroi", "@anthicus", "@tecta", "@collaris", "@elude", "@avert",
+    "@obviate", "@quell", "@disrupt", "@evoke", "@incite", "@emanate",
+];
+
+#[rustfmt::skip]
+pub const BLOCKS: &[&str] = &[
+    // Environment
+    "@air", "@stone", "@sand-floor", "@darksand", "@grass", "@ice", "@snow", "@deep-water",
+    "@shallow-water", "@tar", "@metal-floor", "@spawn", "@ore-copper", "@ore-lead",
+    "@ore-scrap", "@ore-coal", "@ore-titanium", "@ore-thorium", "@ore-beryllium",
+    "@ore-tungsten",
+    // Turrets
+    "@duo", "@scatter", "@scorch", "@hail", "@wave", "@lancer", "@arc", "@parallax", "@swarmer",
+    "@salvo", "@segment", "@tsunami", "@fuse", "@ripple", "@cyclone", "@foreshadow", "@spectre",
+    "@meltdown", "@breach", "@diffuse", "@sublimate", "@titan", "@disperse", "@afflict",
+    "@lustre", "@scathe", "@smite", "@malign",
+    // Production
+    "@mechanical-drill", "@pneumatic-drill", "@laser-drill", "@blast-drill", "@water-extractor",
+    "@cultivator", "@oil-extractor", "@cliff-crusher", "@plasma-bore", "@large-plasma-bore",
+    "@impact-drill", "@eruption-drill", "@vent-condenser",
+    // Crafting
+    "@graphite-press", "@multi-press", "@silicon-smelter", "@silicon-crucible", "@kiln",
+    "@plastanium-compressor", "@phase-weaver", "@surge-smelter", "@cryofluid-mixer",
+    "@pyratite-mixer", "@blast-mixer", "@melter", "@separator", "@disassembler", "@spore-press",
+    "@pulverizer", "@coal-centrifuge", "@incinerator", "@silicon-arc-furnace", "@electrolyzer",
+    "@atmospheric-concentrator", "@oxidation-chamber", "@electric-heater", "@slag-heater",
+    "@phase-heater", "@heat-redirector", "@heat-router", "@slag-incinerator",
+    "@carbide-crucible", "@slag-centrifuge", "@surge-crucible", "@cyanogen-synthesizer",
+    "@phase-synthesizer", "@heat-reactor",
+    // Distribution
+    "@conveyor", "@titanium-conveyor", "@plastanium-conveyor", "@armored-conveyor", "@junction",
+    "@bridge-conveyor", "@phase-conveyor", "@sorter", "@inverted-sorter", "@router",
+    "@distributor", "@overflow-gate", "@underflow-gate", "@mass-driver", "@duct",
+    "@armored-duct", "@duct-router", "@overflow-duct", "@underflow-duct", "@duct-bridge",
+    "@duct-unloader", "@surge-conveyor", "@surge-router", "@unit-cargo-loader",
+    "@unit-cargo-unload-point",
+    // Liquids
+    "@mechanical-pump", "@rotary-pump", "@impulse-pump", "@conduit", "@pulse-conduit",
+    "@plated-conduit", "@liquid-router", "@liquid-container", "@liquid-tank",
+    "@liquid-junction", "@bridge-conduit", "@phase-conduit", "@reinforced-pump",
+    "@reinforced-conduit", "@reinforced-liquid-junction", "@reinforced-bridge-conduit",
+    "@reinforced-liquid-router", "@reinforced-liquid-container", "@reinforced-liquid-tank",
+    // Power
+    "@power-node", "@power-node-large", "@surge-tower", "@diode", "@battery", "@battery-large",
+    "@combustion-generator", "@thermal-generator", "@steam-generator",
+    "@differential-generator", "@rtg-generator", "@solar-panel", "@large-solar-panel",
+    "@thorium-reactor", "@impact-reactor", "@power-source", "@power-void", "@beam-node",
+    "@beam-tower", "@beam-link", "@turbine-condenser", "@chemical-combustion-chamber",
+    "@pyrolysis-generator", "@flux-reactor", "@neoplasia-reactor",
+    // Defense
+    "@copper-wall", "@copper-wall-large", "@titanium-wall", "@titanium-wall-large",
+    "@plastanium-wall", "@plastanium-wall-large", "@thorium-wall", "@thorium-wall-large",
+    "@phase-wall", "@phase-wall-large", "@surge-wall", "@surge-wall-large", "@door",
+    "@door-large", "@scrap-wall", "@scrap-wall-large", "@scrap-wall-huge",
+    "@scrap-wall-gigantic", "@beryllium-wall", "@beryllium-wall-large", "@tungsten-wall",
+    "@tungsten-wall-large", "@blast-door", "@reinforced-surge-wall",
+    "@reinforced-surge-wall-large", "@carbide-wall", "@carbide-wall-large", "@shielded-wall",
+    "@mender", "@mend-projector", "@overdrive-projector", "@overdrive-dome",
+    "@force-projector", "@shock-mine", "@radar", "@build-tower", "@regen-projector",
+    "@shockwave-tower",
+    // Storage
+    "@core-shard", "@core-foundation", "@core-nucleus", "@core-bastion", "@core-citadel",
+    "@core-acropolis", "@container", "@vault", "@unloader", "@reinforced-container",
+    "@reinforced-vault",
+    // Units
+    "@ground-factory", "@air-factory", "@naval-factory", "@additive-reconstructor",
+    "@multiplicative-reconstructor", "@exponential-reconstructor",
+    "@tetrative-reconstructor", "@repair-point", "@repair-turret", "@payload-conveyor",
+    "@payload-router", "@tank-fabricator", "@ship-fabricator", "@mech-fabricator",
+    "@tank-refabricator", "@ship-refabricator", "@mech-refabricator", "@prime-refabricator",
+    "@tank-assembler", "@ship-assembler", "@mech-assembler", "@basic-assembler-module",
+    "@unit-repair-tower", "@reinforced-payload-conveyor", "@reinforced-payload-router",
+    "@payload-mass-driver", "@large-payload-mass-driver", "@small-deconstructor",
+    "@deconstructor", "@constructor", "@large-constructor", "@payload-loader",
+    "@payload-unloader",
+    // Logic
+    "@message", "@switch", "@micro-processor", "@logic-processor", "@hyper-processor",
+    "@memory-cell", "@memory-bank", "@logic-display", "@large-logic-display", "@canvas",
+    "@reinforced-message", "@world-processor", "@world-cell", "@world-message",
+    "@world-switch", "@illuminator",
+    // Sandbox
+    "@item-source", "@item-void", "@liquid-source", "@liquid-void", "@payload-source",
+    "@payload-void", "@heat-source",
+];
+
+/// Whether `name` is a known content, team or sensor constant. These are
+/// plain values, unlike the processor-dependent `VARIABLES`.
+pub fn is_content(name: &str) -> bool {
+    SENSORS.iter().any(|sensor| sensor.name == name)
+        || [TEAMS, ITEMS, LIQUIDS, UNITS, BLOCKS]
+            .iter()
+            .any(|names| names.contains(&name))
+}
+
+/// Whether `name` is an `@` built-in: a processor variable, a math constant
+/// or content.
+pub fn is_builtin(name: &str) -> bool {
+    VARIABLES.contains(&name) || math_constant(name).is_some() || is_content(name)
+}
+
+/// Whether `name` is what a processor calls a linked building, such as
+/// `cell1` for a memory cell: the last word of the block's name, skipping a
+/// trailing `large`, followed by a number.
+pub fn is_link(name: &str) -> bool {
+    let word = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if word.is_empty() || word.len() == name.len() {
+        return false;
+    }
+
+    BLOCKS.iter().any(|block| {
+        let mut words = block[1..].rsplit('-');
+        match words.next() {
+            Some("large") => words.next() == Some(word),
+            last => last == Some(word),
+        }
+    })
+}
+
+/// How many numbers a linked memory block holds, if `block` names a cell or
+/// bank.
+pub fn memory_capacity(block: &str) -> Option<usize> {
+    if !is_link(block) {
+        return None;
+    }
+    match block.trim_end_matches(|c: char| c.is_ascii_digit()) {
+        "cell" => Some(64),
+        "bank" => Some(512),
+        _ => None,
+    }
+}
+
+pub fn math_constant(name: &str) -> Option<f32> {
+    MATH_CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, value)| *value)
+}
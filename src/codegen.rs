@@ -0,0 +1,2131 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use instruction::{assemble, Instruction, Label};
+
+use crate::{
+    builtins,
+    error::{CompileError, Diagnostic, Stage},
+    expr::{
+        Assign, Binary, Builtin, Call, CompoundAssign, Conditional, Control, Draw, Expr, ExprLike,
+        Get, Global, Grouping, Index, Interpolation, Literal, Part, SetIndex, Unary, Variable,
+    },
+    stmt::{
+        Array, Block, Destructure, Expression, For, Function, If, Jump, Link, Return, Stmt, Var,
+        While,
+    },
+    token::{Object as Ob, Token, TokenType as TT},
+};
+
+pub mod instruction;
+
+/// How many `draw` commands a processor buffers before dropping the rest
+/// until the next `drawflush`.
+const DRAW_BUFFER: usize = 256;
+
+pub struct Output {
+    pub code: String,
+    pub result: String,
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+/// Jump targets of an enclosing loop.
+#[derive(Clone)]
+struct LoopLabels {
+    name: Option<String>,
+    continue_label: Label,
+    break_label: Label,
+}
+
+/// Calling convention of a user-defined function: arguments are stored in
+/// `params`, the caller's resume address in `return_address`, and the callee
+/// leaves its value in `result` before `set @counter`-ing back.
+#[derive(Clone)]
+struct FunctionInfo {
+    params: Vec<String>,
+    result: String,
+    return_address: String,
+    entry: Label,
+}
+
+/// A function body whose code is emitted after the main program.
+#[derive(Clone)]
+struct PendingBody {
+    name: String,
+    params: Vec<Token>,
+    body: Vec<Stmt>,
+    /// The global scope when the function was declared, so the body can't
+    /// see globals declared after it.
+    globals: HashMap<String, Binding>,
+}
+
+/// A linked memory cell or bank used as a call stack, which recursive
+/// functions need because their parameters and locals are static variables.
+/// Only numbers survive a round trip through memory, so calls that would save
+/// anything else are rejected.
+#[derive(Clone)]
+pub struct StackConfig {
+    pub block: String,
+    pub size: usize,
+}
+
+/// The Mindustry version the generated code must run on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    V7,
+    /// Adds `select`, `format` and `unpackcolor`.
+    V8,
+}
+
+/// Where an array's elements live in memory.
+#[derive(Clone)]
+struct Slots {
+    block: String,
+    offset: usize,
+    size: usize,
+}
+
+/// What a name in scope stands for.
+#[derive(Clone)]
+enum Binding {
+    /// The mlog variable holding a variable's value.
+    Variable(String),
+    Array(Slots),
+}
+
+#[derive(Clone)]
+pub struct Compiler {
+    code: Vec<Instruction>,
+    temps: usize,
+    labels: usize,
+    scopes: Vec<HashMap<String, Binding>>,
+    names: HashSet<String>,
+    loops: Vec<LoopLabels>,
+    functions: HashMap<String, FunctionInfo>,
+    pending: Vec<PendingBody>,
+    function: Option<String>,
+    /// The return address, parameters and locals of the function being
+    /// compiled, which calls save on the stack.
+    frame: Vec<String>,
+    /// Temporaries of the current statement, which are saved with the frame.
+    live: Vec<String>,
+    /// Everything saved on the stack by each call, with the calling function
+    /// and the callee, to check that it's all numbers.
+    spills: Vec<(Vec<String>, String, Token)>,
+    calls: Vec<(String, Token)>,
+    stack: Option<StackConfig>,
+    stack_pointer: Option<String>,
+    overflow: Option<Label>,
+    target: Target,
+    /// How many commands may be in the draw buffer, counting the longer
+    /// branch of each `if`.
+    draw_commands: usize,
+    /// How many `draw` and `drawflush` instructions have been emitted, to
+    /// tell whether a loop draws without flushing.
+    draws: usize,
+    flushes: usize,
+    display: Option<String>,
+    /// How many slots of each memory block are taken by arrays or the stack.
+    memory: HashMap<String, usize>,
+    /// Whether the text buffer is known to hold no `{0}` to `{9}` text,
+    /// which `format` would fill in before an interpolation's placeholders.
+    clean_buffer: bool,
+    /// Whether the next instruction can be reached from the one before it.
+    falls_through: bool,
+    /// `clean_buffer` joined over the jumps to each label not yet emitted,
+    /// and as it was at each label already emitted.
+    jumps_clean: HashMap<Label, bool>,
+    labels_clean: HashMap<Label, bool>,
+    /// Labels jumped back to with a less clean buffer than the code after
+    /// them assumed, whose loop must be emitted again.
+    unclean_jumps: HashSet<Label>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self {
+            code: vec![],
+            temps: 0,
+            labels: 0,
+            scopes: vec![HashMap::new()],
+            names: HashSet::new(),
+            loops: vec![],
+            functions: HashMap::new(),
+            pending: vec![],
+            function: None,
+            frame: vec![],
+            live: vec![],
+            spills: vec![],
+            calls: vec![],
+            stack: None,
+            stack_pointer: None,
+            overflow: None,
+            target: Target::default(),
+            draw_commands: 0,
+            draws: 0,
+            flushes: 0,
+            display: None,
+            memory: HashMap::new(),
+            clean_buffer: true,
+            falls_through: true,
+            jumps_clean: HashMap::new(),
+            labels_clean: HashMap::new(),
+            unclean_jumps: HashSet::new(),
+            warnings: vec![],
+        }
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generate(expr: &impl Compilable) -> Result<Output, CompileError> {
+        let mut compiler = Compiler::new();
+        let result = expr.compile(&mut compiler)?;
+
+        Ok(Output {
+            code: assemble(&compiler.code),
+            result,
+        })
+    }
+
+    /// Uses `stack` as the call stack, which must fit in a memory cell or
+    /// bank.
+    pub fn with_stack(mut self, stack: StackConfig) -> Result<Self, String> {
+        let Some(capacity) = builtins::memory_capacity(&stack.block) else {
+            return Err(format!(
+                "The call stack must be in a memory cell or bank, not '{}'.",
+                stack.block
+            ));
+        };
+        if stack.size == 0 || stack.size > capacity {
+            return Err(format!(
+                "The call stack needs 1 to {capacity} slots of '{}' but got {}.",
+                stack.block, stack.size
+            ));
+        }
+        self.stack = Some(stack);
+        Ok(self)
+    }
+
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn generate_program(statements: &[Stmt]) -> Result<String, CompileError> {
+        Compiler::new().compile_program(statements)
+    }
+
+    pub fn compile_program(&mut self, statements: &[Stmt]) -> Result<String, CompileError> {
+        for statement in statements {
+            statement.emit(self)?;
+        }
+        let main_end = self.code.len();
+        self.emit_functions()?;
+        self.check_spills()?;
+
+        // Text that is printed but never flushed goes to the first message
+        // block once the main program is done.
+        let printed = |prefix: &str| {
+            self.code.iter().any(
+                |instruction| matches!(instruction, Instruction::Op(line) if line.starts_with(prefix)),
+            )
+        };
+        if printed("print ") && !printed("printflush ") {
+            self.code
+                .insert(main_end, Instruction::Op("printflush message1".to_owned()));
+        }
+
+        if let Some(stack_pointer) = &self.stack_pointer {
+            self.code
+                .insert(0, Instruction::Op(format!("set {stack_pointer} 0")));
+        }
+        if let Some(overflow) = self.overflow {
+            self.emit_label(overflow);
+            self.emit("print \"Stack overflow\"".to_owned());
+            self.emit("stop".to_owned());
+        }
+
+        Ok(assemble(&self.code))
+    }
+
+    /// Warnings raised while compiling, which don't stop the code from being
+    /// generated.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Reserves `size` slots of `block`, returning the first one. Arrays are
+    /// placed after the call stack when they share its block.
+    fn allocate(&mut self, block: &str, size: usize) -> usize {
+        let reserved = match &self.stack {
+            Some(stack) if stack.block == block => stack.size,
+            _ => 0,
+        };
+        let used = self.memory.entry(block.to_owned()).or_insert(reserved);
+        let offset = *used;
+        *used += size;
+        offset
+    }
+
+    /// Allocates a fresh temporary variable name.
+    pub fn temp(&mut self) -> String {
+        loop {
+            let name = format!("__t{}", self.temps);
+            self.temps += 1;
+            if self.names.insert(name.clone()) {
+                if self.function.is_some() {
+                    self.live.push(name.clone());
+                }
+                return name;
+            }
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Reserves an mlog variable name derived from `base` that nothing else in
+    /// the program uses.
+    pub fn fresh(&mut self, base: &str) -> String {
+        let mut mangled = base.to_owned();
+        let mut suffix = 0;
+        while !self.names.insert(mangled.clone()) {
+            suffix += 1;
+            mangled = format!("{base}_{suffix}");
+        }
+        mangled
+    }
+
+    /// Binds `name` in the innermost scope to a fresh mlog variable, so
+    /// shadowed variables stay distinct. Locals of a function are prefixed
+    /// with its name.
+    pub fn declare(&mut self, name: &str) -> String {
+        let mangled = match &self.function {
+            Some(function) => {
+                let mangled = self.fresh(&format!("__{function}_{name}"));
+                self.frame.push(mangled.clone());
+                mangled
+            }
+            None => self.fresh(name),
+        };
+        self.bind(name, Binding::Variable(mangled.clone()));
+        mangled
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        self.scopes
+            .last_mut()
+            .expect("compiler always has a global scope")
+            .insert(name.to_owned(), binding);
+    }
+
+    /// The innermost binding of `name`.
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The mlog variable of the variable `name`, unless an array shadows it.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        match self.lookup(name)? {
+            Binding::Variable(mangled) => Some(mangled.to_owned()),
+            Binding::Array(_) => None,
+        }
+    }
+
+    /// Where the elements of the array `name` are, unless a variable
+    /// shadows it.
+    fn array(&self, name: &str) -> Option<&Slots> {
+        match self.lookup(name)? {
+            Binding::Array(slots) => Some(slots),
+            Binding::Variable(_) => None,
+        }
+    }
+
+    /// Whether `variable` is bound in the global scope, where any function
+    /// body can assign it.
+    fn is_global(&self, variable: &str) -> bool {
+        self.scopes[0]
+            .values()
+            .any(|binding| matches!(binding, Binding::Variable(mangled) if mangled == variable))
+    }
+
+    /// Compiles `operands` left to right. An operand that names a variable is
+    /// copied to a temporary when a later operand assigns that variable, so
+    /// each operand keeps the value it had when it was evaluated.
+    pub fn compile_operands(
+        &mut self,
+        operands: &[&dyn Compilable],
+    ) -> Result<Vec<String>, CompileError> {
+        let mut values = Vec::with_capacity(operands.len());
+        for (i, operand) in operands.iter().enumerate() {
+            let mut value = operand.compile(self)?;
+            if operands[i + 1..]
+                .iter()
+                .any(|later| later.assigns(self, &value))
+            {
+                let copy = self.temp();
+                self.emit(format!("set {copy} {value}"));
+                value = copy;
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    pub fn emit(&mut self, line: String) {
+        self.code.push(Instruction::Op(line));
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        self.labels += 1;
+        Label(self.labels - 1)
+    }
+
+    pub fn emit_label(&mut self, label: Label) {
+        let jumped = self.jumps_clean.remove(&label).unwrap_or(true);
+        self.clean_buffer = jumped && (self.clean_buffer || !self.falls_through);
+        self.falls_through = true;
+        self.labels_clean.insert(label, self.clean_buffer);
+        self.code.push(Instruction::Label(label));
+    }
+
+    pub fn emit_jump(&mut self, label: Label, condition: String) {
+        match self.labels_clean.get(&label) {
+            Some(&clean) => {
+                if clean && !self.clean_buffer {
+                    self.unclean_jumps.insert(label);
+                }
+            }
+            None => *self.jumps_clean.entry(label).or_insert(true) &= self.clean_buffer,
+        }
+        self.falls_through = !condition.starts_with("always");
+        self.code.push(Instruction::Jump(label, condition));
+    }
+
+    /// Emits `print` or `format` of `value`, after which the text buffer may
+    /// hold placeholder text unless `value` is a constant without braces.
+    fn emit_print(&mut self, instruction: &str, value: String) {
+        let plain = match value.strip_prefix('"') {
+            Some(text) => !text.contains('{'),
+            None => {
+                matches!(value.as_str(), "null" | "true" | "false")
+                    || value.starts_with('%')
+                    || value.parse::<f64>().is_ok()
+            }
+        };
+        self.clean_buffer &= plain;
+        self.emit(format!("{instruction} {value}"));
+    }
+
+    /// Emits a loop with `emit`. Code at the start of the loop assumes the
+    /// text buffer is as clean as on entry; if the end of the body or a
+    /// `continue` proves otherwise, the loop is emitted again without it.
+    fn emit_loop(
+        &mut self,
+        emit: impl Fn(&mut Self) -> Result<(), CompileError>,
+    ) -> Result<(), CompileError> {
+        if !self.clean_buffer {
+            return emit(self);
+        }
+        let first = self.labels;
+        let before = self.clone();
+        emit(self)?;
+        if self.unclean_jumps.iter().any(|label| label.0 >= first) {
+            *self = before;
+            self.clean_buffer = false;
+            emit(self)?;
+        }
+        Ok(())
+    }
+
+    /// Emits a `draw` command, flushing to the last flushed display once the
+    /// draw buffer is full. Loops are checked separately by `in_loop`.
+    fn emit_draw(&mut self, instruction: String, token: &Token) {
+        if self.draw_commands >= DRAW_BUFFER {
+            match self.display.to_owned() {
+                Some(display) => self.emit_drawflush(display),
+                None => self.warnings.push(
+                    Diagnostic::warning(
+                        Stage::Compile,
+                        token,
+                        format!(
+                            "More than {DRAW_BUFFER} draw commands before 'drawflush'; the rest are dropped."
+                        ),
+                    )
+                    .with_help("call 'drawflush' on a display before this".to_owned()),
+                ),
+            }
+        }
+
+        self.emit(instruction);
+        self.draw_commands += 1;
+        self.draws += 1;
+    }
+
+    fn emit_drawflush(&mut self, display: String) {
+        self.emit(format!("drawflush {display}"));
+        self.display = Some(display);
+        self.draw_commands = 0;
+        self.flushes += 1;
+    }
+
+    /// Jumps to `label` when the truthiness of `value` equals `when`. Folded
+    /// constant conditions need no test at all.
+    pub fn emit_branch(&mut self, label: Label, value: &str, when: bool) {
+        let truthy = match value {
+            "true" => Some(true),
+            "false" | "null" | "0" => Some(false),
+            _ => None,
+        };
+
+        match truthy {
+            Some(truthy) if truthy == when => self.emit_jump(label, "always 0 0".to_owned()),
+            Some(_) => {}
+            None if when => self.emit_jump(label, format!("notEqual {value} false")),
+            None => self.emit_jump(label, format!("equal {value} false")),
+        }
+    }
+
+    /// Runs `body` with `break`/`continue` (optionally labelled `name`)
+    /// targeting the given labels. A body that draws without flushing fills
+    /// the draw buffer after enough iterations, which is warned about at
+    /// the `keyword` of the outermost such loop, as an enclosing loop may
+    /// flush each time round.
+    fn in_loop(
+        &mut self,
+        name: &Option<Token>,
+        keyword: &Token,
+        continue_label: Label,
+        break_label: Label,
+        body: impl FnOnce(&mut Self) -> Result<(), CompileError>,
+    ) -> Result<(), CompileError> {
+        self.loops.push(LoopLabels {
+            name: name.as_ref().map(|name| name.lexeme.to_owned()),
+            continue_label,
+            break_label,
+        });
+        let (draw_commands, draws, flushes) = (self.draw_commands, self.draws, self.flushes);
+        let result = body(self);
+        self.loops.pop();
+
+        if self.loops.is_empty() && self.draws > draws && self.flushes == flushes {
+            self.warnings.push(
+                Diagnostic::warning(
+                    Stage::Compile,
+                    keyword,
+                    "Loop draws without 'drawflush', so the draw buffer fills up and later commands are dropped.".to_owned(),
+                )
+                .with_help("call 'drawflush' on a display inside the loop".to_owned()),
+            );
+        }
+        // The loop may run no times at all.
+        self.draw_commands = self.draw_commands.max(draw_commands);
+        result
+    }
+
+    /// Appends the bodies of every declared function after an `end` that
+    /// keeps the main program from falling through into them.
+    fn emit_functions(&mut self) -> Result<(), CompileError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.emit("end".to_owned());
+
+        for pending in std::mem::take(&mut self.pending) {
+            let info = &self.functions[&pending.name];
+            let entry = info.entry;
+            let result = info.result.to_owned();
+            let return_address = info.return_address.to_owned();
+            let params = info.params.clone();
+
+            let hidden = self.scopes.split_off(1);
+            let globals = std::mem::replace(&mut self.scopes[0], pending.globals);
+            self.push_scope();
+            self.frame = vec![return_address.to_owned()];
+            for (param, mangled) in pending.params.iter().zip(params) {
+                self.frame.push(mangled.clone());
+                self.bind(&param.lexeme, Binding::Variable(mangled));
+            }
+            self.function = Some(pending.name);
+
+            self.emit_label(entry);
+            self.clean_buffer = false;
+            let body = pending
+                .body
+                .iter()
+                .try_for_each(|statement| statement.emit(self));
+
+            self.function = None;
+            self.scopes.truncate(1);
+            self.scopes[0] = globals;
+            self.scopes.extend(hidden);
+            body?;
+
+            self.emit(format!("set {result} null"));
+            self.emit(format!("set @counter {return_address}"));
+        }
+
+        if self.stack.is_none() {
+            self.check_recursion()?;
+        }
+        Ok(())
+    }
+
+    /// Rejects calls that can re-enter their caller, since without a stack
+    /// the inner activation would overwrite the outer one's variables.
+    fn check_recursion(&self) -> Result<(), CompileError> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (caller, callee) in &self.calls {
+            edges.entry(caller).or_default().push(&callee.lexeme);
+        }
+
+        for (caller, callee) in &self.calls {
+            let mut seen = HashSet::new();
+            let mut todo = vec![callee.lexeme.as_str()];
+            while let Some(function) = todo.pop() {
+                if function == caller {
+                    return Err(CompileError::new(
+                        callee,
+                        format!(
+                            "Recursive call to '{}' needs a call stack in a memory block.",
+                            callee.lexeme
+                        ),
+                    )
+                    .with_note(
+                        "each function's variables are shared by all of its calls".to_owned(),
+                    )
+                    .with_help("compile with a stack, for example `--stack cell1:64`".to_owned()));
+                }
+                if seen.insert(function) {
+                    todo.extend(edges.get(function).into_iter().flatten());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pushes `values` onto the memory stack, jumping to the overflow handler
+    /// when the block is too small to hold them.
+    fn spill(&mut self, values: &[String]) {
+        let Some(stack) = &self.stack else {
+            return;
+        };
+        if values.is_empty() {
+            return;
+        }
+        let block = stack.block.to_owned();
+        let limit = stack.size as isize - values.len() as isize;
+
+        let stack_pointer = match &self.stack_pointer {
+            Some(stack_pointer) => stack_pointer.to_owned(),
+            None => {
+                let stack_pointer = self.fresh("__sp");
+                self.stack_pointer = Some(stack_pointer.clone());
+                stack_pointer
+            }
+        };
+        let overflow = match self.overflow {
+            Some(overflow) => overflow,
+            None => {
+                let overflow = self.new_label();
+                self.overflow = Some(overflow);
+                overflow
+            }
+        };
+
+        self.emit_jump(overflow, format!("greaterThan {stack_pointer} {limit}"));
+        for value in values {
+            self.emit(format!("write {value} {block} {stack_pointer}"));
+            self.emit(format!("op add {stack_pointer} {stack_pointer} 1"));
+        }
+    }
+
+    /// Rejects calls that save something other than a number on the stack,
+    /// since memory cells would turn it into a number.
+    fn check_spills(&self) -> Result<(), CompileError> {
+        let objects = objects(&self.code);
+        for (values, caller, callee) in &self.spills {
+            let Some(value) = values.iter().find(|value| objects.contains(*value)) else {
+                continue;
+            };
+            let subject = match value.strip_prefix(&format!("__{caller}_")) {
+                Some(local) => format!("Local '{local}'"),
+                None => "A value computed before the call".to_owned(),
+            };
+            return Err(CompileError::new(
+                callee,
+                format!("{subject} may not be a number, so it can't be saved on the call stack."),
+            )
+            .with_note("memory cells and banks only store numbers".to_owned())
+            .with_help(
+                "keep buildings, units and text in globals, which calls don't save".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pops what `spill` pushed, in reverse order.
+    fn restore(&mut self, values: &[String]) {
+        let (Some(stack), Some(stack_pointer)) = (&self.stack, &self.stack_pointer) else {
+            return;
+        };
+        let block = stack.block.to_owned();
+        let stack_pointer = stack_pointer.to_owned();
+
+        for value in values.iter().rev() {
+            self.emit(format!("op sub {stack_pointer} {stack_pointer} 1"));
+            self.emit(format!("read {value} {block} {stack_pointer}"));
+        }
+    }
+}
+
+pub trait Compilable {
+    /// Emits the instructions computing this node and returns the operand
+    /// (a variable name or a constant) holding its value.
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError>;
+    /// Emits a jump to `label` taken when the truthiness of this node's value
+    /// equals `when`, falling through otherwise.
+    fn compile_branch(
+        &self,
+        compiler: &mut Compiler,
+        label: Label,
+        when: bool,
+    ) -> Result<(), CompileError> {
+        let value = self.compile(compiler)?;
+        compiler.emit_branch(label, &value, when);
+        Ok(())
+    }
+    /// Compiles a node returning several values, one operand per value.
+    fn compile_tuple(&self, compiler: &mut Compiler) -> Result<Vec<String>, CompileError> {
+        Ok(vec![self.compile(compiler)?])
+    }
+    /// Appends this node's value to the text buffer.
+    fn compile_print(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let value = self.compile(compiler)?;
+        compiler.emit_print("print", value);
+        Ok(())
+    }
+    /// The node's value if it is known at compile time.
+    fn as_constant(&self) -> Option<Ob> {
+        None
+    }
+    /// Whether compiling this node emits no instructions and only names its
+    /// value, like a literal or a variable.
+    fn is_operand(&self) -> bool {
+        false
+    }
+    /// Whether running this node may assign to the mlog variable `variable`,
+    /// in which case operands compiled before it must copy it first.
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool;
+    fn report_error(&self, token: &Token, message: String) -> CompileError {
+        CompileError::new(token, message)
+    }
+}
+
+pub trait Emittable {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError>;
+    fn report_error(&self, token: &Token, message: String) -> CompileError {
+        CompileError::new(token, message)
+    }
+}
+
+impl Compilable for Expr {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        self.0.borrow().compile(compiler)
+    }
+
+    fn compile_branch(
+        &self,
+        compiler: &mut Compiler,
+        label: Label,
+        when: bool,
+    ) -> Result<(), CompileError> {
+        self.0.borrow().compile_branch(compiler, label, when)
+    }
+
+    fn compile_tuple(&self, compiler: &mut Compiler) -> Result<Vec<String>, CompileError> {
+        self.0.borrow().compile_tuple(compiler)
+    }
+
+    fn compile_print(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.0.borrow().compile_print(compiler)
+    }
+
+    fn as_constant(&self) -> Option<Ob> {
+        self.0.borrow().as_constant()
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.0.borrow().assigns(compiler, variable)
+    }
+
+    fn is_operand(&self) -> bool {
+        self.0.borrow().is_operand()
+    }
+}
+
+impl Compilable for Unary {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let right = self.right.compile(compiler)?;
+        if self.operator.ttype == TT::Plus {
+            return Ok(right);
+        }
+
+        let result = compiler.temp();
+        match self.operator.ttype {
+            TT::Minus => compiler.emit(format!("op sub {result} 0 {right}")),
+            TT::Tilde => compiler.emit(format!("op not {result} {right}")),
+            TT::Not => compiler.emit(format!("op equal {result} {right} false")),
+            _ => panic!(
+                "Unexpected {} in unary compilation, should not occur.",
+                self.operator.lexeme
+            ),
+        }
+
+        Ok(result)
+    }
+    fn compile_branch(
+        &self,
+        compiler: &mut Compiler,
+        label: Label,
+        when: bool,
+    ) -> Result<(), CompileError> {
+        if self.operator.ttype == TT::Not {
+            return self.right.compile_branch(compiler, label, !when);
+        }
+
+        let value = self.compile(compiler)?;
+        compiler.emit_branch(label, &value, when);
+        Ok(())
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.right.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for Binary {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        if matches!(self.operator.ttype, TT::And | TT::Or) {
+            // Only evaluate the right operand when the left one doesn't
+            // already decide the result.
+            let left = self.left.compile(compiler)?;
+            let result = compiler.temp();
+            let end_label = compiler.new_label();
+            compiler.emit(format!("set {result} {left}"));
+            compiler.emit_branch(end_label, &result, self.operator.ttype == TT::Or);
+            let right = self.right.compile(compiler)?;
+            compiler.emit(format!("set {result} {right}"));
+            compiler.emit_label(end_label);
+            return Ok(result);
+        }
+
+        let [left, right] = compiler
+            .compile_operands(&[&*self.left, &*self.right])?
+            .try_into()
+            .expect("two operands compile to two values");
+
+        let op = binary_op(&self.operator);
+        let result = compiler.temp();
+        compiler.emit(format!("op {op} {result} {left} {right}"));
+        Ok(result)
+    }
+
+    fn compile_branch(
+        &self,
+        compiler: &mut Compiler,
+        label: Label,
+        when: bool,
+    ) -> Result<(), CompileError> {
+        match (self.operator.ttype, when) {
+            (TT::And, false) | (TT::Or, true) => {
+                self.left.compile_branch(compiler, label, when)?;
+                self.right.compile_branch(compiler, label, when)
+            }
+            (TT::And, true) | (TT::Or, false) => {
+                let skip_label = compiler.new_label();
+                self.left.compile_branch(compiler, skip_label, !when)?;
+                self.right.compile_branch(compiler, label, when)?;
+                compiler.emit_label(skip_label);
+                Ok(())
+            }
+            (ttype, _) => {
+                // Comparisons map straight onto `jump` conditions.
+                let condition = match (ttype, when) {
+                    (TT::LAngle, true) | (TT::RAngleEquals, false) => "lessThan",
+                    (TT::LAngleEquals, true) | (TT::RAngle, false) => "lessThanEq",
+                    (TT::RAngle, true) | (TT::LAngleEquals, false) => "greaterThan",
+                    (TT::RAngleEquals, true) | (TT::LAngle, false) => "greaterThanEq",
+                    (TT::Equals2, true) | (TT::BangEquals, false) => "equal",
+                    (TT::BangEquals, true) | (TT::Equals2, false) => "notEqual",
+                    (TT::Equals3, true) => "strictEqual",
+                    _ => {
+                        let value = self.compile(compiler)?;
+                        compiler.emit_branch(label, &value, when);
+                        return Ok(());
+                    }
+                };
+
+                let [left, right] = compiler
+                    .compile_operands(&[&*self.left, &*self.right])?
+                    .try_into()
+                    .expect("two operands compile to two values");
+                compiler.emit_jump(label, format!("{condition} {left} {right}"));
+                Ok(())
+            }
+        }
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.left.assigns(compiler, variable) || self.right.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for Conditional {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        // The same check as the interpreter's, but by the types the branches
+        // are known to have, since neither runs at compile time.
+        if let (Some(then_type), Some(else_type)) =
+            (self.then_branch.dtype(), self.else_branch.dtype())
+        {
+            if then_type != "Null" && else_type != "Null" && then_type != else_type {
+                return Err(self
+                    .report_error(
+                        &self.keyword,
+                        format!(
+                            "Conditional branch types `{then_type}` and `{else_type}` are incompatible."
+                        ),
+                    )
+                    .with_label(self.then_branch.span(), format!("this is `{then_type}`"))
+                    .with_label(self.else_branch.span(), format!("this is `{else_type}`")));
+            }
+        }
+
+        let result = compiler.temp();
+
+        // `select` evaluates both branches, so it only fits when neither
+        // needs any instructions of its own.
+        if compiler.target == Target::V8
+            && self.then_branch.is_operand()
+            && self.else_branch.is_operand()
+        {
+            let condition = self.condition.compile(compiler)?;
+            let then_value = self.then_branch.compile(compiler)?;
+            let else_value = self.else_branch.compile(compiler)?;
+            compiler.emit(format!(
+                "select {result} notEqual {condition} false {then_value} {else_value}"
+            ));
+            return Ok(result);
+        }
+
+        let else_label = compiler.new_label();
+        self.condition.compile_branch(compiler, else_label, false)?;
+        self.compile_diamond(compiler, &result, else_label)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        [&self.condition, &self.then_branch, &self.else_branch]
+            .iter()
+            .any(|child| child.assigns(compiler, variable))
+    }
+}
+
+impl Conditional {
+    /// Emits both branches around a jump to `else_label`, which the caller
+    /// has already branched to when the condition is false.
+    fn compile_diamond(
+        &self,
+        compiler: &mut Compiler,
+        result: &str,
+        else_label: Label,
+    ) -> Result<String, CompileError> {
+        let end_label = compiler.new_label();
+        let then_value = self.then_branch.compile(compiler)?;
+        compiler.emit(format!("set {result} {then_value}"));
+        compiler.emit_jump(end_label, "always 0 0".to_owned());
+        compiler.emit_label(else_label);
+        let else_value = self.else_branch.compile(compiler)?;
+        compiler.emit(format!("set {result} {else_value}"));
+        compiler.emit_label(end_label);
+        Ok(result.to_owned())
+    }
+}
+
+impl Compilable for Grouping {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        self.expression.compile(compiler)
+    }
+
+    fn compile_branch(
+        &self,
+        compiler: &mut Compiler,
+        label: Label,
+        when: bool,
+    ) -> Result<(), CompileError> {
+        self.expression.compile_branch(compiler, label, when)
+    }
+
+    fn compile_tuple(&self, compiler: &mut Compiler) -> Result<Vec<String>, CompileError> {
+        self.expression.compile_tuple(compiler)
+    }
+
+    fn compile_print(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.expression.compile_print(compiler)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.expression.assigns(compiler, variable)
+    }
+
+    fn is_operand(&self) -> bool {
+        self.expression.is_operand()
+    }
+}
+
+impl Compilable for Literal {
+    fn compile(&self, _compiler: &mut Compiler) -> Result<String, CompileError> {
+        Ok(constant(&self.value))
+    }
+
+    fn as_constant(&self) -> Option<Ob> {
+        Some(self.value.to_owned())
+    }
+
+    fn assigns(&self, _compiler: &Compiler, _variable: &str) -> bool {
+        false
+    }
+
+    fn is_operand(&self) -> bool {
+        true
+    }
+}
+
+impl Compilable for Variable {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        if let Some(name) = compiler.resolve(&self.name.lexeme) {
+            return Ok(name);
+        }
+
+        Err(self.report_error(
+            &self.name,
+            format!("Undefined variable '{}'.", self.name.lexeme),
+        ))
+    }
+
+    fn assigns(&self, _compiler: &Compiler, _variable: &str) -> bool {
+        false
+    }
+
+    fn is_operand(&self) -> bool {
+        true
+    }
+}
+
+impl Compilable for Interpolation {
+    fn compile(&self, _compiler: &mut Compiler) -> Result<String, CompileError> {
+        Err(self
+            .report_error(
+                &self.string,
+                "Strings with non-constant '{}' parts can only be printed.".to_owned(),
+            )
+            .with_help("pass it straight to 'print', which prints each part in turn".to_owned()))
+    }
+
+    fn compile_print(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let mut braces = false;
+        let mut placeholders = 0;
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => braces |= text.contains(['{', '}']),
+                Part::Value(_) => placeholders += 1,
+            }
+        }
+
+        if compiler.target != Target::V8 || braces || placeholders > 10 {
+            for part in &self.parts {
+                match part {
+                    Part::Text(text) => {
+                        compiler.emit_print("print", constant(&Ob::String(text.to_owned())))
+                    }
+                    Part::Value(value) => value.compile_print(compiler)?,
+                }
+            }
+            return Ok(());
+        }
+
+        let values: Vec<&dyn Compilable> = self
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Value(value) => Some(&**value as &dyn Compilable),
+                Part::Text(_) => None,
+            })
+            .collect();
+        let mut values = compiler.compile_operands(&values)?.into_iter();
+
+        // `format` fills in the lowest numbered `{0}` to `{9}` placeholder
+        // left in the text buffer, so the whole string can be printed at once
+        // unless earlier text may hold one.
+        if compiler.clean_buffer {
+            let mut template = String::new();
+            let mut count = 0;
+            for part in &self.parts {
+                match part {
+                    Part::Text(text) => template.push_str(text),
+                    Part::Value(_) => {
+                        template.push_str(&format!("{{{count}}}"));
+                        count += 1;
+                    }
+                }
+            }
+            compiler.emit(format!("print {}", constant(&Ob::String(template))));
+            for value in values {
+                compiler.emit_print("format", value);
+            }
+            return Ok(());
+        }
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => {
+                    compiler.emit_print("print", constant(&Ob::String(text.to_owned())))
+                }
+                Part::Value(_) => compiler.emit_print("print", values.next().unwrap()),
+            }
+        }
+        Ok(())
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.parts.iter().any(|part| match part {
+            Part::Text(_) => false,
+            Part::Value(value) => value.assigns(compiler, variable),
+        })
+    }
+}
+
+impl Compilable for Index {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let (block, address) = element(compiler, &self.name, &self.bracket, &*self.index)?;
+        let result = compiler.temp();
+        compiler.emit(format!("read {result} {block} {address}"));
+        Ok(result)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.index.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for SetIndex {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let (block, mut address) = element(compiler, &self.name, &self.bracket, &*self.index)?;
+        if self.value.assigns(compiler, &address) {
+            let copy = compiler.temp();
+            compiler.emit(format!("set {copy} {address}"));
+            address = copy;
+        }
+        let value = match &self.operator {
+            Some(operator) => {
+                // The element is read once, before `value` is evaluated.
+                let current = compiler.temp();
+                compiler.emit(format!("read {current} {block} {address}"));
+                let value = self.value.compile(compiler)?;
+                let result = compiler.temp();
+                compiler.emit(format!(
+                    "op {} {result} {current} {value}",
+                    binary_op(operator)
+                ));
+                result
+            }
+            None => self.value.compile(compiler)?,
+        };
+        compiler.emit(format!("write {value} {block} {address}"));
+        Ok(value)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.index.assigns(compiler, variable) || self.value.assigns(compiler, variable)
+    }
+}
+
+/// The mlog variables that may hold something other than a number (such as
+/// a building, unit or string) somewhere in `code`. `null` reads back from
+/// memory as 0, which mlog already treats as equal, so it counts as a number.
+fn objects(code: &[Instruction]) -> HashSet<String> {
+    let object = |operand: &str, objects: &HashSet<String>| {
+        operand.starts_with('"')
+            || (operand.starts_with('@')
+                && !builtins::NUMERIC_VARIABLES.contains(&operand)
+                && builtins::math_constant(operand).is_none())
+            || builtins::is_link(operand)
+            || objects.contains(operand)
+    };
+
+    let mut objects = HashSet::new();
+    loop {
+        let before = objects.len();
+        for instruction in code {
+            let Instruction::Op(line) = instruction else {
+                continue;
+            };
+            let words = words(line);
+            let outputs: Vec<&str> = match words[..] {
+                ["set", output, value] if object(value, &objects) => vec![output],
+                ["select", output, _, _, _, then, otherwise]
+                    if object(then, &objects) || object(otherwise, &objects) =>
+                {
+                    vec![output]
+                }
+                ["sensor", output, _, property] => {
+                    match builtins::sensor_property(&property[1..]) {
+                        Some(sensor) if ["Number", "Boolean", "Colour"].contains(&sensor.dtype) => {
+                            vec![]
+                        }
+                        _ => vec![output],
+                    }
+                }
+                ["getlink", output, _] => vec![output],
+                ["uradar", .., output] | ["ulocate", .., output] => vec![output],
+                ["ucontrol", "getBlock", _, _, kind, building, floor] => {
+                    vec![kind, building, floor]
+                }
+                _ => vec![],
+            };
+            objects.extend(outputs.into_iter().map(str::to_owned));
+        }
+        if objects.len() == before {
+            return objects;
+        }
+    }
+}
+
+/// Splits an mlog instruction into its words, keeping quoted strings whole.
+fn words(line: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let end = match rest.strip_prefix('"') {
+            Some(string) => string.find('"').map_or(rest.len(), |end| end + 2),
+            None => rest.find(' ').unwrap_or(rest.len()),
+        };
+        words.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    words
+}
+
+/// Borrows each argument as an operand for [`Compiler::compile_operands`].
+fn operands(arguments: &[Box<dyn ExprLike>]) -> Vec<&dyn Compilable> {
+    arguments.iter().map(|argument| &**argument as _).collect()
+}
+
+/// The memory block and address of `name[index]`, rejecting constant
+/// indices that are out of bounds.
+fn element(
+    compiler: &mut Compiler,
+    name: &Token,
+    bracket: &Token,
+    index: &dyn Compilable,
+) -> Result<(String, String), CompileError> {
+    let error = |message: String| CompileError::new(bracket, message);
+
+    let Some(Slots {
+        block,
+        offset,
+        size,
+    }) = compiler.array(&name.lexeme)
+    else {
+        return Err(error(format!("Undefined array '{}'.", name.lexeme)));
+    };
+    let (block, offset, size) = (block.to_owned(), *offset, *size);
+
+    let address = match index.as_constant() {
+        Some(Ob::Number(index)) => {
+            let element = index.floor();
+            if element < 0. || element as usize >= size {
+                return Err(error(format!(
+                    "Index {index} is out of bounds for '{}' of size {size}.",
+                    name.lexeme
+                )));
+            }
+            (offset + element as usize).to_string()
+        }
+        Some(index) => {
+            return Err(error(format!(
+                "Index type `{}` is invalid, expected `Number`.",
+                index.dtype()
+            )))
+        }
+        None if offset == 0 => index.compile(compiler)?,
+        None => {
+            let index = index.compile(compiler)?;
+            let address = compiler.temp();
+            compiler.emit(format!("op add {address} {index} {offset}"));
+            address
+        }
+    };
+    Ok((block, address))
+}
+
+impl Compilable for Assign {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let value = self.value.compile(compiler)?;
+        let Some(target) = compiler.resolve(&self.name.lexeme) else {
+            return Err(self.report_error(
+                &self.name,
+                format!("Undefined variable '{}'.", self.name.lexeme),
+            ));
+        };
+
+        compiler.emit(format!("set {target} {value}"));
+        Ok(target)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        compiler.resolve(&self.name.lexeme).as_deref() == Some(variable)
+            || self.value.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for CompoundAssign {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let Some(target) = compiler.resolve(&self.name.lexeme) else {
+            return Err(self.report_error(
+                &self.name,
+                format!("Undefined variable '{}'.", self.name.lexeme),
+            ));
+        };
+        let [current, value] = compiler
+            .compile_operands(&[&*self.operation.left, &*self.operation.right])?
+            .try_into()
+            .expect("two operands compile to two values");
+
+        let op = binary_op(&self.operation.operator);
+        compiler.emit(format!("op {op} {target} {current} {value}"));
+        Ok(target)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        compiler.resolve(&self.name.lexeme).as_deref() == Some(variable)
+            || self.operation.right.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for Call {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let Some(arity) = compiler
+            .functions
+            .get(&self.callee.lexeme)
+            .map(|function| function.params.len())
+        else {
+            return Err(self.report_error(
+                &self.callee,
+                format!("Undefined function '{}'.", self.callee.lexeme),
+            ));
+        };
+
+        if arity != self.arguments.len() {
+            return Err(self.report_error(
+                &self.paren,
+                format!(
+                    "Expected {} arguments but got {}.",
+                    arity,
+                    self.arguments.len()
+                ),
+            ));
+        }
+
+        let mut arguments = compiler.compile_operands(&operands(&self.arguments))?;
+
+        // Parameters are set one by one, so an argument reading an earlier
+        // parameter of the same function (as in a recursive call) needs a
+        // copy before it is overwritten.
+        let params = compiler.functions[&self.callee.lexeme].params.clone();
+        for (i, argument) in arguments.iter_mut().enumerate() {
+            if params[..i].contains(argument) {
+                let copy = compiler.temp();
+                compiler.emit(format!("set {copy} {argument}"));
+                *argument = copy;
+            }
+        }
+
+        // Inside a function any call may re-enter it, so its live state goes
+        // on the stack around the call.
+        let frame = match &compiler.function {
+            Some(caller) => {
+                compiler
+                    .calls
+                    .push((caller.to_owned(), self.callee.to_owned()));
+                let frame = [compiler.frame.as_slice(), &compiler.live].concat();
+                if compiler.stack.is_some() {
+                    compiler.spills.push((
+                        frame.clone(),
+                        caller.to_owned(),
+                        self.callee.to_owned(),
+                    ));
+                }
+                frame
+            }
+            None => vec![],
+        };
+        compiler.spill(&frame);
+
+        let function = &compiler.functions[&self.callee.lexeme];
+        let mut lines: Vec<String> = function
+            .params
+            .iter()
+            .zip(arguments)
+            .map(|(param, argument)| format!("set {param} {argument}"))
+            .collect();
+        lines.push(format!("op add {} @counter 1", function.return_address));
+        let entry = function.entry;
+        let result = function.result.to_owned();
+
+        for line in lines {
+            compiler.emit(line);
+        }
+        compiler.emit_jump(entry, "always 0 0".to_owned());
+        // The function returns here, having printed who knows what.
+        compiler.falls_through = true;
+        compiler.clean_buffer = false;
+        compiler.restore(&frame);
+
+        let value = compiler.temp();
+        compiler.emit(format!("set {value} {result}"));
+        Ok(value)
+    }
+
+    /// The function body may assign any global.
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        compiler.is_global(variable)
+            || self
+                .arguments
+                .iter()
+                .any(|argument| argument.assigns(compiler, variable))
+    }
+}
+
+impl Compilable for Builtin {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        if self.callee.lexeme == "print" {
+            for argument in &self.arguments {
+                argument.compile_print(compiler)?;
+            }
+            return Ok("null".to_owned());
+        }
+
+        let mut arguments = compiler.compile_operands(&operands(&self.arguments))?;
+
+        let result = match self.callee.lexeme.as_str() {
+            // Degrees are erased to plain numbers in mlog.
+            "num" | "deg" => return Ok(arguments.remove(0)),
+            "rgb" => {
+                let result = compiler.temp();
+                compiler.emit(format!("packcolor {result} {} 1", arguments.join(" ")));
+                return Ok(result);
+            }
+            "rgba" => {
+                let result = compiler.temp();
+                compiler.emit(format!("packcolor {result} {}", arguments.join(" ")));
+                return Ok(result);
+            }
+            "blend" => return self.compile_blend(compiler, &arguments[2]),
+            "getlink" => {
+                let result = compiler.temp();
+                compiler.emit(format!("getlink {result} {}", arguments[0]));
+                return Ok(result);
+            }
+            "printflush" => {
+                compiler.emit(format!("printflush {}", arguments[0]));
+                compiler.clean_buffer = true;
+                return Ok("null".to_owned());
+            }
+            "drawflush" => {
+                compiler.emit_drawflush(arguments.remove(0));
+                return Ok("null".to_owned());
+            }
+            "ubind" => {
+                compiler.emit(format!("ubind {}", arguments[0]));
+                return Ok("@unit".to_owned());
+            }
+            "move" | "approach" | "mine" | "itemDrop" | "itemTake" | "boost" | "flag" => {
+                arguments.resize(5, "0".to_owned());
+                compiler.emit(format!(
+                    "ucontrol {} {}",
+                    self.callee.lexeme,
+                    arguments.join(" ")
+                ));
+                return Ok("null".to_owned());
+            }
+            "within" => {
+                let result = compiler.temp();
+                compiler.emit(format!(
+                    "ucontrol within {} {result} 0",
+                    arguments.join(" ")
+                ));
+                return Ok(result);
+            }
+            "uradar" => {
+                self.check_keywords()?;
+                let [first, second, third, sort] =
+                    [0, 1, 2, 3].map(|index| self.keyword(index).unwrap_or_default());
+                let order = &arguments[4];
+                let result = compiler.temp();
+                compiler.emit(format!(
+                    "uradar {first} {second} {third} {sort} 0 {order} {result}"
+                ));
+                return Ok(result);
+            }
+            "getBlock" | "ulocate" => {
+                let callee = match &self.unit {
+                    Some(unit) => format!("{}.{}", unit.lexeme, self.callee.lexeme),
+                    None => self.callee.lexeme.to_owned(),
+                };
+                return Err(self
+                    .report_error(
+                        &self.callee,
+                        format!("'{callee}' returns several values, which must be destructured."),
+                    )
+                    .with_help(format!(
+                        "name each value with `var (a, b, ...) = {callee}(...);`"
+                    )));
+            }
+            _ => compiler.temp(),
+        };
+        arguments.resize(2, "0".to_owned());
+        compiler.emit(format!(
+            "op {} {result} {}",
+            self.callee.lexeme,
+            arguments.join(" ")
+        ));
+        Ok(result)
+    }
+
+    fn compile_tuple(&self, compiler: &mut Compiler) -> Result<Vec<String>, CompileError> {
+        match self.callee.lexeme.as_str() {
+            "getBlock" => {
+                let arguments = compiler.compile_operands(&operands(&self.arguments))?;
+                let outputs = [compiler.temp(), compiler.temp(), compiler.temp()];
+                compiler.emit(format!(
+                    "ucontrol getBlock {} {}",
+                    arguments.join(" "),
+                    outputs.join(" ")
+                ));
+                Ok(outputs.to_vec())
+            }
+            "ulocate" => {
+                self.check_keywords()?;
+                let arguments = compiler.compile_operands(&operands(&self.arguments))?;
+                let kind = self.keyword(0).unwrap_or_default();
+                // Arguments a kind doesn't use are written as the defaults
+                // the game's editor fills in.
+                let [group, enemy, ore] = match (kind.as_str(), arguments.as_slice()) {
+                    (_, [_, _, enemy, ore]) => [
+                        self.keyword(1).unwrap_or_default(),
+                        enemy.to_owned(),
+                        ore.to_owned(),
+                    ],
+                    ("building", [_, _, enemy]) => [
+                        self.keyword(1).unwrap_or_default(),
+                        enemy.to_owned(),
+                        "@copper".to_owned(),
+                    ],
+                    ("ore", [_, ore]) => ["core", "true", ore].map(|a| a.to_owned()),
+                    ("spawn" | "damaged", [_]) => ["core", "true", "@copper"].map(|a| a.to_owned()),
+                    _ => {
+                        return Err(self
+                            .report_error(
+                                &self.paren,
+                                format!(
+                                    "'ulocate' of kind '{kind}' can't take {} arguments.",
+                                    arguments.len()
+                                ),
+                            )
+                            .with_help(
+                                "call it as `ulocate(\"building\", group, enemy)`, \
+                                 `ulocate(\"ore\", ore)`, `ulocate(\"spawn\")` or \
+                                 `ulocate(\"damaged\")`"
+                                    .to_owned(),
+                            ))
+                    }
+                };
+                let [found, x, y, building] = [
+                    compiler.temp(),
+                    compiler.temp(),
+                    compiler.temp(),
+                    compiler.temp(),
+                ];
+                compiler.emit(format!(
+                    "ulocate {kind} {group} {enemy} {ore} {x} {y} {found} {building}"
+                ));
+                Ok(vec![found, x, y, building])
+            }
+            _ => Ok(vec![self.compile(compiler)?]),
+        }
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        (self.callee.lexeme == "ubind" && variable == "@unit")
+            || self
+                .arguments
+                .iter()
+                .any(|argument| argument.assigns(compiler, variable))
+    }
+}
+
+impl Builtin {
+    /// The word written for argument `index`, if it is a constant string.
+    fn keyword(&self, index: usize) -> Option<String> {
+        match self.arguments[index].as_constant() {
+            Some(Ob::String(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    /// Rejects word arguments that aren't one of the words the instruction
+    /// accepts.
+    fn check_keywords(&self) -> Result<(), CompileError> {
+        for index in 0..self.arguments.len() {
+            let Some(words) = builtins::keywords(&self.callee.lexeme, index, self.arguments.len())
+            else {
+                continue;
+            };
+            if !self
+                .keyword(index)
+                .is_some_and(|word| words.contains(&word.as_str()))
+            {
+                return Err(self.report_error(
+                    &self.callee,
+                    format!(
+                        "Argument {} of '{}' must be one of {}.",
+                        index + 1,
+                        self.callee.lexeme,
+                        quoted(words)
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Blends two constant colours by a runtime factor, interpolating each
+    /// component before packing the result.
+    fn compile_blend(&self, compiler: &mut Compiler, factor: &str) -> Result<String, CompileError> {
+        let (Some(Ob::Colour(r1, g1, b1, a1)), Some(Ob::Colour(r2, g2, b2, a2))) = (
+            self.arguments[0].as_constant(),
+            self.arguments[1].as_constant(),
+        ) else {
+            return Err(self.report_error(
+                &self.callee,
+                "'blend' can only mix constant colours.".to_owned(),
+            ));
+        };
+
+        let t = compiler.temp();
+        compiler.emit(format!("op max {t} {factor} 0"));
+        compiler.emit(format!("op min {t} {t} 1"));
+
+        let mut components = vec![];
+        for (from, to) in [(r1, r2), (g1, g2), (b1, b2), (a1, a2)] {
+            let component = compiler.temp();
+            compiler.emit(format!("op mul {component} {t} {}", to - from));
+            compiler.emit(format!("op add {component} {component} {from}"));
+            components.push(component);
+        }
+
+        let result = compiler.temp();
+        compiler.emit(format!("packcolor {result} {}", components.join(" ")));
+        Ok(result)
+    }
+}
+
+impl Compilable for Get {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        // Properties of constants are folded away, so anything left is read
+        // from a building or unit.
+        if let Some(object) = self.object.as_constant() {
+            return Err(self.report_error(
+                &self.name,
+                format!(
+                    "Type `{}` has no property '{}'.",
+                    object.dtype(),
+                    self.name.lexeme
+                ),
+            ));
+        }
+        let components = builtins::COLOUR_COMPONENTS;
+        if let Some(index) = components.iter().position(|c| *c == self.name.lexeme) {
+            if compiler.target != Target::V8 {
+                return Err(self
+                    .report_error(
+                        &self.name,
+                        "Colour components of non-constant values need 'unpackcolor'.".to_owned(),
+                    )
+                    .with_help("compile with `--target v8`".to_owned()));
+            }
+            let colour = self.object.compile(compiler)?;
+            let results: Vec<String> = components.iter().map(|_| compiler.temp()).collect();
+            compiler.emit(format!("unpackcolor {} {colour}", results.join(" ")));
+            return Ok(results[index].to_owned());
+        }
+        if builtins::sensor_property(&self.name.lexeme).is_none() {
+            return Err(self.report_error(
+                &self.name,
+                format!("Unknown sensor property '{}'.", self.name.lexeme),
+            ));
+        }
+
+        let object = self.object.compile(compiler)?;
+        let result = compiler.temp();
+        compiler.emit(format!("sensor {result} {object} @{}", self.name.lexeme));
+        Ok(result)
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.object.assigns(compiler, variable)
+    }
+}
+
+impl Compilable for Control {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let mut operands = operands(&self.arguments);
+        operands.insert(0, &*self.object);
+        let mut arguments = compiler.compile_operands(&operands)?;
+        let object = arguments.remove(0);
+
+        arguments.resize(4, "0".to_owned());
+        compiler.emit(format!(
+            "control {} {object} {}",
+            self.name.lexeme,
+            arguments.join(" ")
+        ));
+        Ok("null".to_owned())
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.object.assigns(compiler, variable)
+            || self
+                .arguments
+                .iter()
+                .any(|argument| argument.assigns(compiler, variable))
+    }
+}
+
+impl Compilable for Draw {
+    fn compile(&self, compiler: &mut Compiler) -> Result<String, CompileError> {
+        let mut arguments = compiler.compile_operands(&operands(&self.arguments))?;
+
+        if self.callee.lexeme == "print" {
+            let align = match self.arguments[2].as_constant() {
+                Some(Ob::String(align)) if builtins::TEXT_ALIGNS.contains(&align.as_str()) => align,
+                _ => {
+                    return Err(self.report_error(
+                        &self.callee,
+                        format!(
+                            "Argument 3 of 'draw.print' must be one of {}.",
+                            quoted(builtins::TEXT_ALIGNS)
+                        ),
+                    ))
+                }
+            };
+            arguments[2] = align;
+        }
+
+        arguments.resize(6, "0".to_owned());
+        compiler.emit_draw(
+            format!("draw {} {}", self.callee.lexeme, arguments.join(" ")),
+            &self.callee,
+        );
+        Ok("null".to_owned())
+    }
+
+    fn assigns(&self, compiler: &Compiler, variable: &str) -> bool {
+        self.arguments
+            .iter()
+            .any(|argument| argument.assigns(compiler, variable))
+    }
+}
+
+impl Compilable for Global {
+    fn compile(&self, _compiler: &mut Compiler) -> Result<String, CompileError> {
+        Ok(self.name.lexeme.to_owned())
+    }
+
+    fn assigns(&self, _compiler: &Compiler, _variable: &str) -> bool {
+        false
+    }
+
+    fn is_operand(&self) -> bool {
+        true
+    }
+}
+
+impl Emittable for Stmt {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        // Temporaries never outlive the statement that computes them.
+        compiler.live.clear();
+        self.0.borrow().emit(compiler)
+    }
+}
+
+impl Emittable for Expression {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        self.expression.compile(compiler)?;
+        Ok(())
+    }
+}
+
+impl Emittable for Var {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let value = match &self.initializer {
+            Some(initializer) => initializer.compile(compiler)?,
+            None => constant(&Ob::Null),
+        };
+        let target = compiler.declare(&self.name.lexeme);
+        compiler.emit(format!("set {target} {value}"));
+        Ok(())
+    }
+}
+
+impl Emittable for Array {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let Some(block) = compiler.resolve(&self.block.lexeme) else {
+            return Err(self.report_error(
+                &self.block,
+                format!("Undefined memory block '{}'.", self.block.lexeme),
+            ));
+        };
+        let offset = compiler.allocate(&block, self.size);
+
+        let used = offset + self.size;
+        if let Some(capacity) = builtins::memory_capacity(&block) {
+            if used > capacity {
+                return Err(self.report_error(
+                    &self.block,
+                    format!("Arrays in '{block}' need {used} slots but it only holds {capacity}."),
+                ));
+            }
+        }
+
+        compiler.bind(
+            &self.name.lexeme,
+            Binding::Array(Slots {
+                block,
+                offset,
+                size: self.size,
+            }),
+        );
+        Ok(())
+    }
+}
+
+impl Emittable for Destructure {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let values = self.initializer.compile_tuple(compiler)?;
+        if values.len() != self.names.len() {
+            return Err(self.report_error(
+                &self.paren,
+                format!(
+                    "Expected {} values but got {}.",
+                    self.names.len(),
+                    values.len()
+                ),
+            ));
+        }
+
+        for (name, value) in self.names.iter().zip(values) {
+            let target = compiler.declare(&name.lexeme);
+            compiler.emit(format!("set {target} {value}"));
+        }
+        Ok(())
+    }
+}
+
+impl Emittable for Block {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        compiler.push_scope();
+        let result = self
+            .statements
+            .iter()
+            .try_for_each(|statement| statement.emit(compiler));
+        compiler.pop_scope();
+        result
+    }
+}
+
+impl Emittable for If {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let else_label = compiler.new_label();
+        self.condition.compile_branch(compiler, else_label, false)?;
+
+        // Only one branch runs, so the draw buffer holds at most what the
+        // longer one leaves in it.
+        let draw_commands = compiler.draw_commands;
+        self.then_branch.emit(compiler)?;
+
+        match &self.else_branch {
+            Some(else_branch) => {
+                let end_label = compiler.new_label();
+                compiler.emit_jump(end_label, "always 0 0".to_owned());
+                compiler.emit_label(else_label);
+                let then_commands = std::mem::replace(&mut compiler.draw_commands, draw_commands);
+                else_branch.emit(compiler)?;
+                compiler.draw_commands = compiler.draw_commands.max(then_commands);
+                compiler.emit_label(end_label);
+            }
+            None => {
+                compiler.emit_label(else_label);
+                compiler.draw_commands = compiler.draw_commands.max(draw_commands);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Emittable for While {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        compiler.emit_loop(|compiler| {
+            let start_label = compiler.new_label();
+            let end_label = compiler.new_label();
+
+            compiler.emit_label(start_label);
+            if let Some(condition) = &self.condition {
+                condition.compile_branch(compiler, end_label, false)?;
+            }
+
+            compiler.in_loop(
+                &self.label,
+                &self.keyword,
+                start_label,
+                end_label,
+                |compiler| self.body.emit(compiler),
+            )?;
+
+            compiler.emit_jump(start_label, "always 0 0".to_owned());
+            compiler.emit_label(end_label);
+            Ok(())
+        })
+    }
+}
+
+impl Emittable for For {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        compiler.push_scope();
+        let result = self.emit_loop(compiler);
+        compiler.pop_scope();
+        result
+    }
+}
+
+impl For {
+    fn emit_loop(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        if let Some(initializer) = &self.initializer {
+            initializer.emit(compiler)?;
+        }
+
+        compiler.emit_loop(|compiler| {
+            let start_label = compiler.new_label();
+            let continue_label = compiler.new_label();
+            let end_label = compiler.new_label();
+
+            compiler.emit_label(start_label);
+            if let Some(condition) = &self.condition {
+                condition.compile_branch(compiler, end_label, false)?;
+            }
+
+            compiler.in_loop(
+                &self.label,
+                &self.keyword,
+                continue_label,
+                end_label,
+                |compiler| self.body.emit(compiler),
+            )?;
+
+            compiler.emit_label(continue_label);
+            if let Some(increment) = &self.increment {
+                increment.compile(compiler)?;
+            }
+            compiler.emit_jump(start_label, "always 0 0".to_owned());
+            compiler.emit_label(end_label);
+            Ok(())
+        })
+    }
+}
+
+impl Emittable for Jump {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let target = compiler
+            .loops
+            .iter()
+            .rev()
+            .find(|target| match &self.label {
+                Some(label) => target.name.as_deref() == Some(label.lexeme.as_str()),
+                None => true,
+            });
+
+        let Some(target) = target else {
+            return Err(self.report_error(
+                &self.keyword,
+                format!("Cannot '{}' outside of a loop.", self.keyword.lexeme),
+            ));
+        };
+
+        let label = match self.keyword.ttype {
+            TT::Break => target.break_label,
+            _ => target.continue_label,
+        };
+        compiler.emit_jump(label, "always 0 0".to_owned());
+        Ok(())
+    }
+}
+
+impl Emittable for Link {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        // A link is named by the processor, so it can't be renamed like a
+        // variable; a variable already using the name would be overwritten.
+        for name in &self.names {
+            if compiler.resolve(&name.lexeme).as_ref() != Some(&name.lexeme)
+                && !compiler.names.insert(name.lexeme.to_owned())
+            {
+                return Err(self.report_error(
+                    name,
+                    format!("'{}' is already used by a variable.", name.lexeme),
+                ));
+            }
+            compiler.bind(&name.lexeme, Binding::Variable(name.lexeme.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+impl Emittable for Function {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let name = self.name.lexeme.to_owned();
+        if compiler.functions.contains_key(&name) {
+            return Err(self.report_error(
+                &self.name,
+                format!("Function '{name}' is already declared."),
+            ));
+        }
+
+        let params = self
+            .params
+            .iter()
+            .map(|param| compiler.fresh(&format!("__{name}_{}", param.lexeme)))
+            .collect();
+        let info = FunctionInfo {
+            params,
+            result: compiler.fresh(&format!("__{name}_result")),
+            return_address: compiler.fresh(&format!("__{name}_return")),
+            entry: compiler.new_label(),
+        };
+
+        compiler.functions.insert(name.to_owned(), info);
+        compiler.pending.push(PendingBody {
+            name,
+            params: self.params.clone(),
+            body: self.body.clone(),
+            globals: compiler.scopes[0].clone(),
+        });
+        Ok(())
+    }
+}
+
+impl Emittable for Return {
+    fn emit(&self, compiler: &mut Compiler) -> Result<(), CompileError> {
+        let value = match &self.value {
+            Some(value) => value.compile(compiler)?,
+            None => constant(&Ob::Null),
+        };
+
+        let Some(function) = compiler
+            .function
+            .as_ref()
+            .map(|function| &compiler.functions[function])
+        else {
+            return Err(self.report_error(
+                &self.keyword,
+                "Cannot 'return' outside of a function.".to_owned(),
+            ));
+        };
+
+        let lines = [
+            format!("set {} {value}", function.result),
+            format!("set @counter {}", function.return_address),
+        ];
+        for line in lines {
+            compiler.emit(line);
+        }
+        Ok(())
+    }
+}
+
+/// The `op` instruction for an arithmetic, bitwise or comparison operator.
+fn binary_op(operator: &Token) -> &'static str {
+    match operator.ttype {
+        TT::Plus => "add",
+        TT::Minus => "sub",
+        TT::Ast => "mul",
+        TT::Slash => "div",
+        TT::Slash2 => "idiv",
+        TT::Percent => "mod",
+        TT::Ast2 => "pow",
+        TT::Equals2 => "equal",
+        TT::BangEquals => "notEqual",
+        TT::Equals3 => "strictEqual",
+        TT::LAngle => "lessThan",
+        TT::LAngleEquals => "lessThanEq",
+        TT::RAngle => "greaterThan",
+        TT::RAngleEquals => "greaterThanEq",
+        TT::LAngle2 => "shl",
+        TT::RAngle2 => "shr",
+        TT::Amp => "and",
+        TT::Bar => "or",
+        TT::Hat => "xor",
+        _ => panic!(
+            "Unexpected {} in binary compilation, should not occur.",
+            operator.lexeme
+        ),
+    }
+}
+
+/// Lists the words an argument accepts, for error messages.
+fn quoted(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|word| format!("\"{word}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders a compile-time value as an mlog operand.
+pub fn constant(value: &Ob) -> String {
+    match value {
+        Ob::Number(value) | Ob::Degree(value) => format!("{value}"),
+        Ob::String(value) => format!("\"{}\"", value.replace('\n', "\\n")),
+        Ob::Boolean(value) => format!("{value}"),
+        Ob::Colour(..) | Ob::Content(_) => format!("{value}"),
+        Ob::Null => "null".to_owned(),
+    }
+}
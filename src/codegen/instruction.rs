@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(pub usize);
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Op(String),
+    /// `jump` to a label; the condition is everything after the address,
+    /// e.g. `always 0 0` or `equal x false`.
+    Jump(Label, String),
+    /// Marks a jump target. Emits nothing.
+    Label(Label),
+}
+
+/// Resolves every label to the address of the instruction following it and
+/// renders the final mlog text.
+pub fn assemble(instructions: &[Instruction]) -> String {
+    let mut addresses = HashMap::new();
+    let mut address = 0;
+    for instruction in instructions {
+        match instruction {
+            Instruction::Label(label) => {
+                addresses.insert(*label, address);
+            }
+            _ => address += 1,
+        }
+    }
+
+    let mut lines = vec![];
+    for instruction in instructions {
+        match instruction {
+            Instruction::Op(line) => lines.push(line.to_owned()),
+            Instruction::Jump(label, condition) => {
+                let target = addresses[label];
+                lines.push(format!("jump {target} {condition}"));
+            }
+            Instruction::Label(_) => {}
+        }
+    }
+
+    lines.join("\n")
+}
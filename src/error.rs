@@ -1,16 +1,21 @@
 use std::{error, fmt};
 
-use crate::token::Token;
+use crate::token::{Span, Token, TokenType as TT};
 
 #[derive(Debug)]
 pub struct EvaluationError {
-    pub token: Token,
+    pub token: Box<Token>,
     pub message: String,
+    pub annotations: Annotations,
 }
 
 impl EvaluationError {
     pub fn new(token: &Token, message: String) -> Self {
-        Self { token: token.to_owned(), message }
+        Self {
+            token: Box::new(token.to_owned()),
+            message,
+            annotations: Annotations::default(),
+        }
     }
 }
 
@@ -24,13 +29,18 @@ impl fmt::Display for EvaluationError {
 
 #[derive(Debug)]
 pub struct ParseError {
-    pub token: Token,
+    pub token: Box<Token>,
     pub message: String,
+    pub annotations: Annotations,
 }
 
 impl ParseError {
     pub fn new(token: &Token, message: String) -> Self {
-        Self { token: token.to_owned(), message }
+        Self {
+            token: Box::new(token.to_owned()),
+            message,
+            annotations: Annotations::default(),
+        }
     }
 }
 
@@ -41,3 +51,185 @@ impl fmt::Display for ParseError {
         write!(f, "Parsing error.")
     }
 }
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub token: Box<Token>,
+    pub message: String,
+    pub annotations: Annotations,
+}
+
+impl CompileError {
+    pub fn new(token: &Token, message: String) -> Self {
+        Self {
+            token: Box::new(token.to_owned()),
+            message,
+            annotations: Annotations::default(),
+        }
+    }
+}
+
+impl error::Error for CompileError {}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Compile error.")
+    }
+}
+
+impl From<EvaluationError> for CompileError {
+    fn from(error: EvaluationError) -> Self {
+        Self {
+            token: error.token,
+            message: error.message,
+            annotations: error.annotations,
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Diagnostic {
+            annotations: error.annotations,
+            ..Diagnostic::error(Stage::Parse, *error.token, error.message)
+        }
+    }
+}
+
+impl From<EvaluationError> for Diagnostic {
+    fn from(error: EvaluationError) -> Self {
+        Diagnostic {
+            annotations: error.annotations,
+            ..Diagnostic::error(Stage::Evaluation, *error.token, error.message)
+        }
+    }
+}
+
+impl From<CompileError> for Diagnostic {
+    fn from(error: CompileError) -> Self {
+        Diagnostic {
+            annotations: error.annotations,
+            ..Diagnostic::error(Stage::Compile, *error.token, error.message)
+        }
+    }
+}
+
+/// A secondary message pointing at another part of the source, such as the
+/// type of each operand of an invalid operation.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Context attached to an error beyond its main message.
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+macro_rules! annotated {
+    ($($error: ty),*) => {$(
+        impl $error {
+            pub fn with_label(mut self, span: Span, message: String) -> Self {
+                self.annotations.labels.push(Label { span, message });
+                self
+            }
+
+            pub fn with_note(mut self, note: String) -> Self {
+                self.annotations.notes.push(note);
+                self
+            }
+
+            pub fn with_help(mut self, help: String) -> Self {
+                self.annotations.help.push(help);
+                self
+            }
+        }
+    )*};
+}
+
+annotated!(EvaluationError, ParseError, CompileError, Diagnostic);
+
+/// The pass that produced a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Scan,
+    Parse,
+    Evaluation,
+    Compile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// An error or warning found in a script, left to the caller to render.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub stage: Stage,
+    pub span: Span,
+    /// The token the diagnostic points at, if scanning got that far.
+    pub token: Option<Token>,
+    pub message: String,
+    pub annotations: Annotations,
+}
+
+impl Diagnostic {
+    pub fn error(stage: Stage, token: Token, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            stage,
+            span: token.span,
+            token: Some(token),
+            message,
+            annotations: Annotations::default(),
+        }
+    }
+
+    /// An error with no token to point at, such as an unterminated string.
+    pub fn at(stage: Stage, span: Span, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            stage,
+            span,
+            token: None,
+            message,
+            annotations: Annotations::default(),
+        }
+    }
+
+    pub fn warning(stage: Stage, token: &Token, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Diagnostic::error(stage, token.to_owned(), message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Renders as `[ln 1, col 5] Error at 'x': message`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Span { ln, col, .. } = self.span;
+        let severity = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+        write!(f, "[ln {ln}, col {col}] {severity}")?;
+        match &self.token {
+            Some(token) if token.ttype == TT::EOF => write!(f, " at end")?,
+            Some(token) => write!(f, " at '{}'", token.lexeme)?,
+            None => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
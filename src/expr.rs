@@ -5,11 +5,22 @@ use std::{
 };
 
 use crate::{
+    builtins,
+    codegen::Compilable,
     interpreter::Interpretable,
-    token::{Object, Token},
+    optimiser::Foldable,
+    token::{Object, Span, Token, TokenType as TT},
 };
 
-pub trait ExprLike: fmt::Display + Interpretable {}
+pub trait ExprLike: fmt::Display + Interpretable + Compilable + Foldable {
+    /// The source range the expression was parsed from.
+    fn span(&self) -> Span;
+    /// The `Object` type of every value the expression can have, when that
+    /// is known without evaluating it.
+    fn dtype(&self) -> Option<&'static str> {
+        None
+    }
+}
 pub trait IntoExpr {
     fn into_expr(self) -> Expr;
 }
@@ -21,7 +32,16 @@ pub trait IntoExpr {
 #[derive(Clone)]
 pub struct Expr(pub Rc<RefCell<dyn ExprLike>>);
 
-impl ExprLike for Expr {}
+impl ExprLike for Expr {
+    fn span(&self) -> Span {
+        self.0.borrow().span()
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        self.0.borrow().dtype()
+    }
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0.borrow())
@@ -43,7 +63,19 @@ impl Unary {
     }
 }
 
-impl ExprLike for Unary {}
+impl ExprLike for Unary {
+    fn span(&self) -> Span {
+        self.operator.span.to(self.right.span())
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        match self.operator.ttype {
+            TT::Not => Some("Boolean"),
+            TT::Tilde => Some("Number"),
+            _ => self.right.dtype(),
+        }
+    }
+}
 
 impl fmt::Display for Unary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -79,7 +111,38 @@ impl Binary {
     }
 }
 
-impl ExprLike for Binary {}
+impl ExprLike for Binary {
+    fn span(&self) -> Span {
+        self.left.span().to(self.right.span())
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        match self.operator.ttype {
+            TT::LAngle
+            | TT::LAngleEquals
+            | TT::RAngle
+            | TT::RAngleEquals
+            | TT::Equals2
+            | TT::Equals3
+            | TT::BangEquals
+            | TT::And
+            | TT::Or => return Some("Boolean"),
+            TT::Amp | TT::Bar | TT::Hat | TT::LAngle2 | TT::RAngle2 => return Some("Number"),
+            _ => {}
+        }
+        match (self.operator.ttype, self.left.dtype()?, self.right.dtype()?) {
+            (TT::Plus, "String", "String") => Some("String"),
+            (TT::Plus | TT::Minus | TT::Percent, left @ ("Number" | "Degree"), right)
+                if left == right =>
+            {
+                Some(left)
+            }
+            (_, "Number", "Number") => Some("Number"),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Binary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({} {} {})", self.operator.lexeme, self.left, self.right)
@@ -92,21 +155,88 @@ impl IntoExpr for Binary {
     }
 }
 
+// ========== Conditional ==========
+
+/// `if condition then a else b`, or `condition ? a : b`.
+pub struct Conditional {
+    pub keyword: Token,
+    pub condition: Box<dyn ExprLike>,
+    pub then_branch: Box<dyn ExprLike>,
+    pub else_branch: Box<dyn ExprLike>,
+}
+
+impl Conditional {
+    pub fn new(
+        keyword: Token,
+        condition: impl ExprLike + 'static,
+        then_branch: impl ExprLike + 'static,
+        else_branch: impl ExprLike + 'static,
+    ) -> Conditional {
+        Conditional {
+            keyword,
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        }
+    }
+}
+
+impl ExprLike for Conditional {
+    fn span(&self) -> Span {
+        self.keyword
+            .span
+            .to(self.condition.span())
+            .to(self.else_branch.span())
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        let then_type = self.then_branch.dtype()?;
+        (self.else_branch.dtype()? == then_type).then_some(then_type)
+    }
+}
+
+impl fmt::Display for Conditional {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "(? {} {} {})",
+            self.condition, self.then_branch, self.else_branch
+        )
+    }
+}
+
+impl IntoExpr for Conditional {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
 // ========== Grouping ==========
 
 pub struct Grouping {
     pub expression: Box<dyn ExprLike>,
+    pub span: Span,
 }
 
 impl Grouping {
-    pub fn new<'a>(expression: impl ExprLike + 'static) -> Grouping {
+    pub fn new(expression: impl ExprLike + 'static, span: Span) -> Grouping {
         Grouping {
             expression: Box::new(expression),
+            span,
         }
     }
 }
 
-impl ExprLike for Grouping {}
+impl ExprLike for Grouping {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        self.expression.dtype()
+    }
+}
+
 impl fmt::Display for Grouping {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({})", self.expression)
@@ -123,15 +253,25 @@ impl IntoExpr for Grouping {
 
 pub struct Literal {
     pub value: Object,
+    pub span: Span,
 }
 
 impl Literal {
-    pub fn new(value: Object) -> Self {
-        Self { value }
+    pub fn new(value: Object, span: Span) -> Self {
+        Self { value, span }
+    }
+}
+
+impl ExprLike for Literal {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        Some(self.value.dtype())
     }
 }
 
-impl ExprLike for Literal {}
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.value)
@@ -143,3 +283,529 @@ impl IntoExpr for Literal {
         Expr(Rc::new(RefCell::new(self)))
     }
 }
+
+// ===== Variable =====
+
+pub struct Variable {
+    pub name: Token,
+}
+
+impl Variable {
+    pub fn new(name: Token) -> Self {
+        Self { name }
+    }
+}
+
+impl ExprLike for Variable {
+    fn span(&self) -> Span {
+        self.name.span
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name.lexeme)
+    }
+}
+
+impl IntoExpr for Variable {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Interpolation =====
+
+pub enum Part {
+    Text(String),
+    Value(Box<dyn ExprLike>),
+}
+
+/// A string literal with `{expression}`s spliced into it.
+pub struct Interpolation {
+    pub string: Token,
+    pub parts: Vec<Part>,
+}
+
+impl Interpolation {
+    pub fn new(string: Token, parts: Vec<Part>) -> Self {
+        Self { string, parts }
+    }
+}
+
+impl ExprLike for Interpolation {
+    fn span(&self) -> Span {
+        self.string.span
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        Some("String")
+    }
+}
+
+impl fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(format")?;
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => write!(f, " {text:?}")?,
+                Part::Value(value) => write!(f, " {value}")?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoExpr for Interpolation {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Assign =====
+
+pub struct Assign {
+    pub name: Token,
+    pub value: Box<dyn ExprLike>,
+}
+
+impl Assign {
+    pub fn new(name: Token, value: impl ExprLike + 'static) -> Self {
+        Self {
+            name,
+            value: Box::new(value),
+        }
+    }
+}
+
+impl ExprLike for Assign {
+    fn span(&self) -> Span {
+        self.name.span.to(self.value.span())
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        self.value.dtype()
+    }
+}
+
+impl fmt::Display for Assign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(= {} {})", self.name.lexeme, self.value)
+    }
+}
+
+impl IntoExpr for Assign {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== CompoundAssign =====
+
+/// `name op= value`, evaluated as the binary `name op value`.
+pub struct CompoundAssign {
+    pub name: Token,
+    pub operation: Binary,
+}
+
+impl CompoundAssign {
+    pub fn new(name: Token, operator: Token, value: impl ExprLike + 'static) -> Self {
+        Self {
+            operation: Binary::new(operator, Variable::new(name.clone()), value),
+            name,
+        }
+    }
+}
+
+impl ExprLike for CompoundAssign {
+    fn span(&self) -> Span {
+        self.operation.span()
+    }
+}
+
+impl fmt::Display for CompoundAssign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({} {} {})",
+            self.operation.operator.lexeme, self.name.lexeme, self.operation.right
+        )
+    }
+}
+
+impl IntoExpr for CompoundAssign {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Index =====
+
+/// `name[index]`, reading an element of an array.
+pub struct Index {
+    pub name: Token,
+    pub bracket: Token,
+    pub index: Box<dyn ExprLike>,
+    pub span: Span,
+}
+
+impl Index {
+    pub fn new(name: Token, bracket: Token, index: impl ExprLike + 'static, span: Span) -> Self {
+        Self {
+            name,
+            bracket,
+            index: Box::new(index),
+            span,
+        }
+    }
+}
+
+impl ExprLike for Index {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "([] {} {})", self.name.lexeme, self.index)
+    }
+}
+
+impl IntoExpr for Index {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== SetIndex =====
+
+/// `name[index] = value`, writing an element of an array.
+pub struct SetIndex {
+    pub name: Token,
+    pub bracket: Token,
+    pub index: Box<dyn ExprLike>,
+    pub value: Box<dyn ExprLike>,
+    /// The operation combining the element with `value`, as in
+    /// `buf[i] += 1`.
+    pub operator: Option<Token>,
+}
+
+impl SetIndex {
+    pub fn new(
+        name: Token,
+        bracket: Token,
+        index: impl ExprLike + 'static,
+        value: impl ExprLike + 'static,
+    ) -> Self {
+        Self {
+            name,
+            bracket,
+            index: Box::new(index),
+            value: Box::new(value),
+            operator: None,
+        }
+    }
+
+    pub fn with_operator(mut self, operator: Token) -> Self {
+        self.operator = Some(operator);
+        self
+    }
+}
+
+impl ExprLike for SetIndex {
+    fn span(&self) -> Span {
+        self.name.span.to(self.value.span())
+    }
+}
+
+impl fmt::Display for SetIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = self
+            .operator
+            .as_ref()
+            .map_or("=", |operator| &operator.lexeme);
+        write!(
+            f,
+            "([]{operator} {} {} {})",
+            self.name.lexeme, self.index, self.value
+        )
+    }
+}
+
+impl IntoExpr for SetIndex {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Call =====
+
+pub struct Call {
+    pub callee: Token,
+    pub paren: Token,
+    pub arguments: Vec<Box<dyn ExprLike>>,
+}
+
+impl Call {
+    pub fn new(callee: Token, paren: Token, arguments: Vec<Box<dyn ExprLike>>) -> Self {
+        Self {
+            callee,
+            paren,
+            arguments,
+        }
+    }
+}
+
+impl ExprLike for Call {
+    fn span(&self) -> Span {
+        self.callee.span.to(self.paren.span)
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(call {}", self.callee.lexeme)?;
+        for argument in &self.arguments {
+            write!(f, " {argument}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoExpr for Call {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Builtin =====
+
+pub struct Builtin {
+    /// The `@unit` a unit control is called on, as in `@unit.move(x, y)`.
+    pub unit: Option<Token>,
+    pub callee: Token,
+    pub paren: Token,
+    pub arguments: Vec<Box<dyn ExprLike>>,
+}
+
+impl Builtin {
+    pub fn new(callee: Token, paren: Token, arguments: Vec<Box<dyn ExprLike>>) -> Self {
+        Self {
+            unit: None,
+            callee,
+            paren,
+            arguments,
+        }
+    }
+
+    pub fn unit_control(
+        unit: Token,
+        callee: Token,
+        paren: Token,
+        arguments: Vec<Box<dyn ExprLike>>,
+    ) -> Self {
+        Self {
+            unit: Some(unit),
+            callee,
+            paren,
+            arguments,
+        }
+    }
+}
+
+impl ExprLike for Builtin {
+    fn span(&self) -> Span {
+        self.unit
+            .as_ref()
+            .unwrap_or(&self.callee)
+            .span
+            .to(self.paren.span)
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        if let Some(unit) = &self.unit {
+            write!(f, "{}.", unit.lexeme)?;
+        }
+        write!(f, "{}", self.callee.lexeme)?;
+        for argument in &self.arguments {
+            write!(f, " {argument}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoExpr for Builtin {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Get =====
+
+pub struct Get {
+    pub object: Box<dyn ExprLike>,
+    pub name: Token,
+}
+
+impl Get {
+    pub fn new(object: impl ExprLike + 'static, name: Token) -> Self {
+        Self {
+            object: Box::new(object),
+            name,
+        }
+    }
+}
+
+impl ExprLike for Get {
+    fn span(&self) -> Span {
+        self.object.span().to(self.name.span)
+    }
+
+    fn dtype(&self) -> Option<&'static str> {
+        if builtins::COLOUR_COMPONENTS.contains(&self.name.lexeme.as_str()) {
+            return Some("Number");
+        }
+        // Teams and units have no `Object` type of their own.
+        builtins::sensor_property(&self.name.lexeme)
+            .map(|sensor| sensor.dtype)
+            .filter(|dtype| !matches!(*dtype, "Team" | "Unit"))
+    }
+}
+
+impl fmt::Display for Get {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(. {} {})", self.object, self.name.lexeme)
+    }
+}
+
+impl IntoExpr for Get {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Control =====
+
+/// A building control called as a method, such as `turret.shoot(x, y, true)`.
+pub struct Control {
+    pub object: Box<dyn ExprLike>,
+    pub name: Token,
+    pub paren: Token,
+    pub arguments: Vec<Box<dyn ExprLike>>,
+}
+
+impl Control {
+    pub fn new(
+        object: impl ExprLike + 'static,
+        name: Token,
+        paren: Token,
+        arguments: Vec<Box<dyn ExprLike>>,
+    ) -> Self {
+        Self {
+            object: Box::new(object),
+            name,
+            paren,
+            arguments,
+        }
+    }
+}
+
+impl ExprLike for Control {
+    fn span(&self) -> Span {
+        self.object.span().to(self.paren.span)
+    }
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(.{} {}", self.name.lexeme, self.object)?;
+        for argument in &self.arguments {
+            write!(f, " {argument}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoExpr for Control {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Draw =====
+
+/// A display command from the `draw` namespace, such as `draw.line(...)`.
+pub struct Draw {
+    pub callee: Token,
+    pub paren: Token,
+    pub arguments: Vec<Box<dyn ExprLike>>,
+    pub span: Span,
+}
+
+impl Draw {
+    pub fn new(callee: Token, paren: Token, arguments: Vec<Box<dyn ExprLike>>, span: Span) -> Self {
+        Self {
+            callee,
+            paren,
+            arguments,
+            span,
+        }
+    }
+}
+
+impl ExprLike for Draw {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Draw {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(draw.{}", self.callee.lexeme)?;
+        for argument in &self.arguments {
+            write!(f, " {argument}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoExpr for Draw {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ===== Global =====
+
+/// An `@`-prefixed built-in variable or content constant.
+pub struct Global {
+    pub name: Token,
+}
+
+impl Global {
+    pub fn new(name: Token) -> Self {
+        Self { name }
+    }
+}
+
+impl ExprLike for Global {
+    fn span(&self) -> Span {
+        self.name.span
+    }
+}
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name.lexeme)
+    }
+}
+
+impl IntoExpr for Global {
+    fn into_expr(self) -> Expr {
+        Expr(Rc::new(RefCell::new(self)))
+    }
+}
@@ -1,48 +1,184 @@
-use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hasher, RandomState},
+    ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub},
+};
 
 use crate::{
+    builtins,
     error::EvaluationError,
-    expr::{Binary, Expr, Grouping, Literal, Unary},
-    runtime_error,
-    token::{Object as Ob, Token, TokenType as TT},
+    expr::{
+        Assign, Binary, Builtin, Call, CompoundAssign, Conditional, Control, Draw, Expr, ExprLike,
+        Get, Global, Grouping, Index, Interpolation, Literal, Part, SetIndex, Unary, Variable,
+    },
+    stmt::{
+        Array, Block, Destructure, Expression, For, Function, If, Jump, Link, Return, Stmt, Var,
+        While,
+    },
+    token::{Object as Ob, Span, Token, TokenType as TT},
 };
 
+use environment::Environment;
+
 #[macro_use]
 mod macros;
+pub mod environment;
+
+/// A pending `break`, `continue` or `return` unwinding the statements
+/// around it.
+pub enum Flow {
+    Break(Option<String>),
+    Continue(Option<String>),
+    Return(Ob),
+}
 
-pub struct Interpreter {}
+/// Where an array's elements live in memory.
+#[derive(Clone)]
+pub struct Slots {
+    pub block: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+pub struct Callable {
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    /// The globals declared before the function, which are all its body can
+    /// see.
+    pub globals: HashSet<String>,
+}
+
+/// How deeply calls may nest before the interpreter gives up, well before
+/// the native stack would overflow.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Default)]
+pub struct Interpreter {
+    pub environment: Environment,
+    pub functions: HashMap<String, Callable>,
+    /// Buildings declared with `link`, which only exist in game.
+    pub links: HashSet<String>,
+    pub flow: Option<Flow>,
+    /// How many calls are in progress.
+    depth: usize,
+    /// Text printed since the last `printflush`.
+    text: String,
+    /// Everything flushed with `printflush`, one line per flush.
+    pub output: String,
+    /// The slots of each array declaration, so running it again reuses
+    /// them.
+    pub arrays: HashMap<Span, Slots>,
+    /// Contents of the memory blocks arrays are stored in.
+    pub memory: HashMap<String, Vec<f32>>,
+    /// State of the generator behind `rand`.
+    seed: u64,
+}
 
 impl Interpreter {
+    /// An interpreter whose `rand` differs from run to run, like the game's.
     pub fn new() -> Self {
-        Self {}
+        Self::default().with_seed(RandomState::new().build_hasher().finish())
     }
 
-    pub fn interpret(expr: impl Interpretable) {
-        match expr.evaluate() {
-            Ok(value) => println!("{}", value),
-            Err(error) => runtime_error(&error),
+    /// Makes `rand` produce the same numbers on every run with `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), EvaluationError> {
+        for statement in statements {
+            statement.execute(self)?;
+        }
+
+        // Like the compiled program, one that never flushes has its text
+        // flushed once it's done.
+        if self.output.is_empty() && !self.text.is_empty() {
+            self.output = std::mem::take(&mut self.text);
+            self.output.push('\n');
+        }
+        Ok(())
+    }
+
+    /// Uniform random number in `[0, 1)` (splitmix64).
+    fn random(&mut self) -> f32 {
+        self.seed = self.seed.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// The memory address of element `index` of array `name`.
+    fn address(&self, name: &Token, index: &Ob) -> Result<(String, usize), String> {
+        let Some(slots) = self.environment.array(&name.lexeme) else {
+            return Err(format!("Undefined array '{}'.", name.lexeme));
+        };
+        let Ob::Number(index) = index else {
+            return Err(format!(
+                "Index type `{}` is invalid, expected `Number`.",
+                index.dtype()
+            ));
+        };
+
+        let element = index.floor();
+        if element < 0. || element as usize >= slots.size {
+            return Err(format!(
+                "Index {index} is out of bounds for '{}' of size {}.",
+                name.lexeme, slots.size
+            ));
+        }
+        Ok((slots.block.to_owned(), slots.offset + element as usize))
+    }
+
+    /// Settles a pending `break`/`continue` at the end of an iteration of the
+    /// loop labelled `label`, returning whether the loop should stop.
+    fn end_iteration(&mut self, label: &Option<Token>) -> bool {
+        let targets = |target: &Option<String>| match (target, label) {
+            (None, _) => true,
+            (Some(target), Some(label)) => *target == label.lexeme,
+            (Some(_), None) => false,
+        };
+
+        match &self.flow {
+            None => false,
+            Some(Flow::Continue(target)) if targets(target) => {
+                self.flow = None;
+                false
+            }
+            Some(Flow::Break(target)) if targets(target) => {
+                self.flow = None;
+                true
+            }
+            Some(_) => true,
         }
     }
 }
 
 pub trait Interpretable {
-    fn evaluate(&self) -> Result<Ob, EvaluationError>;
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError>;
     fn report_error(&self, token: &Token, message: String) -> EvaluationError {
-        let error = EvaluationError::new(token, message);
-        runtime_error(&error);
-        error
+        EvaluationError::new(token, message)
+    }
+}
+
+pub trait Executable {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError>;
+    fn report_error(&self, token: &Token, message: String) -> EvaluationError {
+        EvaluationError::new(token, message)
     }
 }
 
 impl Interpretable for Expr {
-    fn evaluate(&self) -> Result<Ob, EvaluationError> {
-        self.0.borrow().evaluate()
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        self.0.borrow().evaluate(interpreter)
     }
 }
 
 impl Interpretable for Unary {
-    fn evaluate(&self) -> Result<Ob, EvaluationError> {
-        let eval_right = self.right.evaluate()?;
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let eval_right = self.right.evaluate(interpreter)?;
         match ((self.operator).ttype, &eval_right) {
             (TT::Plus, Ob::Degree(val_right)) => Ok(Ob::Degree(*val_right)),
             (TT::Plus, Ob::Number(val_right)) => Ok(Ob::Number(*val_right)),
@@ -51,10 +187,12 @@ impl Interpretable for Unary {
             (TT::Tilde, Ob::Number(val_right)) => Ok(Ob::Number(-val_right.floor() - 1.)),
             (TT::Not, Ob::Boolean(val_right)) => Ok(Ob::Boolean(!val_right)),
             (TT::Plus | TT::Minus | TT::Tilde | TT::Not, Ob::Null) => Ok(Ob::Null),
-            (TT::Plus | TT::Minus | TT::Tilde | TT::Not, _) => Err(EvaluationError::new(
-                &self.operator,
-                un_err_msg!(self.operator, eval_right),
-            )),
+            (TT::Plus | TT::Minus | TT::Tilde | TT::Not, _) => Err(self
+                .report_error(&self.operator, un_err_msg!(self.operator, eval_right))
+                .with_label(
+                    self.right.span(),
+                    format!("operand is `{}`", eval_right.dtype()),
+                )),
             _ => panic!(
                 "Unexpected {} in unary evaluation, should not occur.",
                 self.operator.lexeme
@@ -64,18 +202,28 @@ impl Interpretable for Unary {
 }
 
 impl Interpretable for Binary {
-    fn evaluate(&self) -> Result<Ob, EvaluationError> {
-        let eval_left = self.left.evaluate()?;
-        let eval_right = self.right.evaluate()?;
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let eval_left = self.left.evaluate(interpreter)?;
+
+        // `and`/`or` skip their right operand once the left decides the result.
+        match (self.operator.ttype, &eval_left) {
+            (TT::And, Ob::Boolean(false)) => return Ok(Ob::Boolean(false)),
+            (TT::Or, Ob::Boolean(true)) => return Ok(Ob::Boolean(true)),
+            _ => {}
+        }
+
+        let eval_right = self.right.evaluate(interpreter)?;
 
         use Ob::*;
         match self.operator.ttype {
             TT::Equals2 => match (&eval_left, &eval_right) {
                 (String(val_left), String(val_right)) => Ok(Boolean(val_left.eq(val_right))),
+                (Content(val_left), Content(val_right)) => Ok(Boolean(val_left.eq(val_right))),
                 _ => Ok(Boolean(eval_left.coerce() == eval_right.coerce())),
             },
             TT::BangEquals => match (&eval_left, &eval_right) {
                 (String(val_left), String(val_right)) => Ok(Boolean(val_left != val_right)),
+                (Content(val_left), Content(val_right)) => Ok(Boolean(val_left != val_right)),
                 _ => Ok(Boolean(eval_left.coerce() != eval_right.coerce())),
             },
             TT::Equals3 => {
@@ -83,6 +231,7 @@ impl Interpretable for Binary {
                     [Number, Number, Boolean],
                     [Degree, Degree, Boolean],
                     [String, String, Boolean],
+                    [Content, Content, Boolean],
                     [Boolean, Boolean, Boolean],
                 }, {
                     [(Null, Null), Ok(Boolean(false))],
@@ -119,7 +268,8 @@ impl Interpretable for Binary {
             }),
             TT::Slash => bin_match!(self, eval_left, eval_right, div, {
                 [Number, Number, Number],
-                [Degree, Degree, Number]
+                [Degree, Degree, Number],
+                [Degree, Number, Degree],
             }),
             TT::Ast => bin_match!(self, eval_left, eval_right, mul, {
                 [Number, Number, Number],
@@ -134,6 +284,7 @@ impl Interpretable for Binary {
             TT::Slash2 => bin_match_deref!(self, eval_left, eval_right, div_euclid, {
                 [Number, Number, Number],
                 [Degree, Degree, Degree],
+                [Degree, Number, Degree],
             }),
 
             TT::Ast2 => bin_match_deref!(self, eval_left, eval_right, powf, {
@@ -148,17 +299,11 @@ impl Interpretable for Binary {
 
             TT::And => match (&eval_left, &eval_right) {
                 (Boolean(val_left), Boolean(val_right)) => Ok(Boolean(*val_left && *val_right)),
-                _ => Err(self.report_error(
-                    &self.operator,
-                    bin_err_msg!(self.operator, eval_left, eval_right),
-                )),
+                _ => Err(self.operand_error(&eval_left, &eval_right)),
             },
             TT::Or => match (&eval_left, &eval_right) {
                 (Boolean(val_left), Boolean(val_right)) => Ok(Boolean(*val_left || *val_right)),
-                _ => Err(self.report_error(
-                    &self.operator,
-                    bin_err_msg!(self.operator, eval_left, eval_right),
-                )),
+                _ => Err(self.operand_error(&eval_left, &eval_right)),
             },
 
             _ => panic!(
@@ -169,14 +314,639 @@ impl Interpretable for Binary {
     }
 }
 
+impl Binary {
+    /// The error for operands the operator doesn't accept, pointing at both.
+    fn operand_error(&self, left: &Ob, right: &Ob) -> EvaluationError {
+        self.report_error(&self.operator, bin_err_msg!(self.operator, left, right))
+            .with_label(
+                self.left.span(),
+                format!("left operand is `{}`", left.dtype()),
+            )
+            .with_label(
+                self.right.span(),
+                format!("right operand is `{}`", right.dtype()),
+            )
+    }
+}
+
+/// The error for a condition that evaluated to `value` rather than a
+/// `Boolean`.
+fn condition_error(keyword: &Token, condition: &dyn ExprLike, value: &Ob) -> EvaluationError {
+    EvaluationError::new(keyword, cond_err_msg!(value))
+        .with_label(condition.span(), format!("this is `{}`", value.dtype()))
+        .with_help("compare it explicitly, for example `x != 0`".to_owned())
+}
+
+impl Interpretable for Conditional {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let condition = match self.condition.evaluate(interpreter)? {
+            Ob::Boolean(condition) => condition,
+            condition => return Err(condition_error(&self.keyword, &*self.condition, &condition)),
+        };
+
+        // Only the taken branch runs, so the other one is checked by the type
+        // it is known to have without evaluating it.
+        let (value, then_type, else_type) = if condition {
+            let value = self.then_branch.evaluate(interpreter)?;
+            (
+                value.to_owned(),
+                Some(value.dtype()),
+                self.else_branch.dtype(),
+            )
+        } else {
+            let value = self.else_branch.evaluate(interpreter)?;
+            (
+                value.to_owned(),
+                self.then_branch.dtype(),
+                Some(value.dtype()),
+            )
+        };
+
+        if let (Some(then_type), Some(else_type)) = (then_type, else_type) {
+            let compatible = then_type == "Null" || else_type == "Null" || then_type == else_type;
+            if !compatible {
+                return Err(self
+                    .report_error(&self.keyword, branch_err_msg!(then_type, else_type))
+                    .with_label(self.then_branch.span(), format!("this is `{then_type}`"))
+                    .with_label(self.else_branch.span(), format!("this is `{else_type}`")));
+            }
+        }
+
+        Ok(value)
+    }
+}
+
 impl Interpretable for Grouping {
-    fn evaluate(&self) -> Result<Ob, EvaluationError> {
-        self.expression.evaluate()
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        self.expression.evaluate(interpreter)
     }
 }
 
 impl Interpretable for Literal {
-    fn evaluate(&self) -> Result<Ob, EvaluationError> {
+    fn evaluate(&self, _interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
         Ok(self.value.to_owned())
     }
 }
+
+impl Interpretable for Variable {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        match interpreter.environment.get(&self.name.lexeme) {
+            Some(value) => Ok(value),
+            None if interpreter.links.contains(&self.name.lexeme) => Err(self.report_error(
+                &self.name,
+                format!(
+                    "Building '{}' can only be evaluated in game.",
+                    self.name.lexeme
+                ),
+            )),
+            None => Err(self.report_error(
+                &self.name,
+                format!("Undefined variable '{}'.", self.name.lexeme),
+            )),
+        }
+    }
+}
+
+impl Interpretable for Interpolation {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let mut string = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => string.push_str(text),
+                Part::Value(value) => string.push_str(&value.evaluate(interpreter)?.to_string()),
+            }
+        }
+        Ok(Ob::String(string))
+    }
+}
+
+impl Interpretable for Index {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let index = self.index.evaluate(interpreter)?;
+        let (block, address) = interpreter
+            .address(&self.name, &index)
+            .map_err(|message| self.report_error(&self.bracket, message))?;
+        Ok(Ob::Number(interpreter.memory[&block][address]))
+    }
+}
+
+impl Interpretable for SetIndex {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let index = self.index.evaluate(interpreter)?;
+        let (block, address) = interpreter
+            .address(&self.name, &index)
+            .map_err(|message| self.report_error(&self.bracket, message))?;
+        let value = match &self.operator {
+            Some(operator) => {
+                // The element is read once, before `value` is evaluated.
+                let current = Ob::Number(interpreter.memory[&block][address]);
+                let value = self.value.evaluate(interpreter)?;
+                let span = self.value.span();
+                Binary::new(
+                    operator.to_owned(),
+                    Literal::new(current, self.bracket.span),
+                    Literal::new(value, span),
+                )
+                .evaluate(interpreter)?
+            }
+            None => self.value.evaluate(interpreter)?,
+        };
+
+        // Memory only holds numbers.
+        let number = match &value {
+            Ob::String(_) | Ob::Content(_) => {
+                return Err(self.report_error(
+                    &self.bracket,
+                    format!("Type `{}` can't be stored in memory.", value.dtype()),
+                ))
+            }
+            value => match value.coerce() {
+                Ob::Number(number) => number,
+                _ => unreachable!("coerce always produces a number"),
+            },
+        };
+        interpreter.memory.get_mut(&block).unwrap()[address] = number;
+        Ok(Ob::Number(number))
+    }
+}
+
+impl Interpretable for Assign {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let value = self.value.evaluate(interpreter)?;
+        if interpreter
+            .environment
+            .assign(&self.name.lexeme, value.clone())
+        {
+            Ok(value)
+        } else {
+            Err(self.report_error(
+                &self.name,
+                format!("Undefined variable '{}'.", self.name.lexeme),
+            ))
+        }
+    }
+}
+
+impl Interpretable for CompoundAssign {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let value = self.operation.evaluate(interpreter)?;
+        if interpreter
+            .environment
+            .assign(&self.name.lexeme, value.clone())
+        {
+            Ok(value)
+        } else {
+            Err(self.report_error(
+                &self.name,
+                format!("Undefined variable '{}'.", self.name.lexeme),
+            ))
+        }
+    }
+}
+
+impl Interpretable for Call {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let Some(function) = interpreter.functions.get(&self.callee.lexeme) else {
+            return Err(self.report_error(
+                &self.callee,
+                format!("Undefined function '{}'.", self.callee.lexeme),
+            ));
+        };
+
+        if function.params.len() != self.arguments.len() {
+            return Err(self.report_error(
+                &self.paren,
+                format!(
+                    "Expected {} arguments but got {}.",
+                    function.params.len(),
+                    self.arguments.len()
+                ),
+            ));
+        }
+
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|param| param.lexeme.to_owned())
+            .collect();
+        let body = function.body.clone();
+        let globals = function.globals.clone();
+
+        let mut arguments = vec![];
+        for argument in &self.arguments {
+            arguments.push(argument.evaluate(interpreter)?);
+        }
+
+        if interpreter.depth == MAX_DEPTH {
+            return Err(self
+                .report_error(
+                    &self.callee,
+                    format!("Calls to '{}' nest too deeply.", self.callee.lexeme),
+                )
+                .with_note(format!(
+                    "at most {MAX_DEPTH} calls can be in progress at once"
+                )));
+        }
+
+        let hidden = interpreter.environment.enter_function(&globals);
+        for (param, argument) in params.iter().zip(arguments) {
+            interpreter.environment.define(param, argument);
+        }
+
+        interpreter.depth += 1;
+        let mut result = Ok(());
+        for statement in &body {
+            result = statement.execute(interpreter);
+            if result.is_err() || interpreter.flow.is_some() {
+                break;
+            }
+        }
+        interpreter.depth -= 1;
+        interpreter.environment.exit_function(hidden);
+        result?;
+
+        match interpreter.flow.take() {
+            Some(Flow::Return(value)) => Ok(value),
+            _ => Ok(Ob::Null),
+        }
+    }
+}
+
+impl Interpretable for Builtin {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        // Without a world there's no message block, so flushed text is
+        // collected in `output` instead.
+        if self.callee.lexeme == "printflush" {
+            let text = std::mem::take(&mut interpreter.text);
+            interpreter.output.push_str(&text);
+            interpreter.output.push('\n');
+            return Ok(Ob::Null);
+        }
+
+        let mut arguments = vec![];
+        for argument in &self.arguments {
+            arguments.push(argument.evaluate(interpreter)?);
+        }
+
+        use Ob::*;
+        let value = match (self.callee.lexeme.as_str(), arguments.as_slice()) {
+            ("max", [Number(a), Number(b)]) => Number(a.max(*b)),
+            ("max", [Degree(a), Degree(b)]) => Degree(a.max(*b)),
+            ("min", [Number(a), Number(b)]) => Number(a.min(*b)),
+            ("min", [Degree(a), Degree(b)]) => Degree(a.min(*b)),
+            ("angle", [Number(x), Number(y)]) => Degree(y.atan2(*x).to_degrees().rem_euclid(360.)),
+            ("angleDiff", [Number(a), Number(b)] | [Degree(a), Degree(b)]) => {
+                let diff = (a - b).rem_euclid(360.);
+                Degree(diff.min(360. - diff))
+            }
+            ("len", [Number(x), Number(y)]) => Number(x.hypot(*y)),
+            ("print", _) => {
+                for argument in &arguments {
+                    interpreter.text.push_str(&argument.to_string());
+                }
+                Null
+            }
+            ("getlink", [Number(_)]) => {
+                return Err(self.report_error(
+                    &self.callee,
+                    "'getlink' can only be evaluated in game.".to_owned(),
+                ))
+            }
+            (name, _)
+                if builtins::UNIT
+                    .iter()
+                    .chain(builtins::UNIT_CONTROLS)
+                    .chain(builtins::FLUSHES)
+                    .any(|signature| signature.name == name) =>
+            {
+                return Err(self.report_error(
+                    &self.callee,
+                    format!("'{name}' can only be evaluated in game."),
+                ))
+            }
+            ("noise", [Number(_), Number(_)]) => {
+                return Err(self.report_error(
+                    &self.callee,
+                    "'noise' can only be evaluated in game.".to_owned(),
+                ))
+            }
+            ("abs", [Number(x)]) => Number(x.abs()),
+            ("abs", [Degree(x)]) => Degree(x.abs()),
+            ("floor", [Number(x)]) => Number(x.floor()),
+            ("floor", [Degree(x)]) => Degree(x.floor()),
+            ("ceil", [Number(x)]) => Number(x.ceil()),
+            ("ceil", [Degree(x)]) => Degree(x.ceil()),
+            ("log", [Number(x)]) => Number(x.ln()),
+            ("log10", [Number(x)]) => Number(x.log10()),
+            ("sqrt", [Number(x)]) => Number(x.sqrt()),
+            ("rand", [Number(x)]) => Number(interpreter.random() * x),
+            ("sin", [Number(x) | Degree(x)]) => Number(x.to_radians().sin()),
+            ("cos", [Number(x) | Degree(x)]) => Number(x.to_radians().cos()),
+            ("tan", [Number(x) | Degree(x)]) => Number(x.to_radians().tan()),
+            ("asin", [Number(x)]) => Degree(x.asin().to_degrees()),
+            ("acos", [Number(x)]) => Degree(x.acos().to_degrees()),
+            ("atan", [Number(x)]) => Degree(x.atan().to_degrees()),
+            ("num", [Number(x) | Degree(x)]) => Number(*x),
+            ("num", [Boolean(x)]) => Number(if *x { 1. } else { 0. }),
+            ("num", [Null]) => Number(0.),
+            ("deg", [Number(x) | Degree(x)]) => Degree(*x),
+            ("rgb", [Number(r), Number(g), Number(b)]) => {
+                Colour(r.clamp(0., 1.), g.clamp(0., 1.), b.clamp(0., 1.), 1.)
+            }
+            ("rgba", [Number(r), Number(g), Number(b), Number(a)]) => Colour(
+                r.clamp(0., 1.),
+                g.clamp(0., 1.),
+                b.clamp(0., 1.),
+                a.clamp(0., 1.),
+            ),
+            ("blend", [Colour(r1, g1, b1, a1), Colour(r2, g2, b2, a2), Number(t)]) => {
+                let t = t.clamp(0., 1.);
+                let lerp = |from: &f32, to: &f32| from + (to - from) * t;
+                Colour(lerp(r1, r2), lerp(g1, g2), lerp(b1, b2), lerp(a1, a2))
+            }
+            _ => {
+                let mut error =
+                    self.report_error(&self.callee, call_err_msg!(self.callee, arguments));
+                for (argument, value) in self.arguments.iter().zip(&arguments) {
+                    error =
+                        error.with_label(argument.span(), format!("this is `{}`", value.dtype()));
+                }
+                return Err(error);
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+impl Interpretable for Get {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        let object = self.object.evaluate(interpreter)?;
+        match (&object, self.name.lexeme.as_str()) {
+            (Ob::Colour(r, ..), "r") => Ok(Ob::Number(*r)),
+            (Ob::Colour(_, g, ..), "g") => Ok(Ob::Number(*g)),
+            (Ob::Colour(_, _, b, _), "b") => Ok(Ob::Number(*b)),
+            (Ob::Colour(.., a), "a") => Ok(Ob::Number(*a)),
+            _ => Err(self.report_error(
+                &self.name,
+                format!(
+                    "Type `{}` has no property '{}'.",
+                    object.dtype(),
+                    self.name.lexeme
+                ),
+            )),
+        }
+    }
+}
+
+impl Interpretable for Control {
+    fn evaluate(&self, interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        self.object.evaluate(interpreter)?;
+        Err(self.report_error(
+            &self.name,
+            format!("'{}' can only be evaluated in game.", self.name.lexeme),
+        ))
+    }
+}
+
+impl Interpretable for Draw {
+    fn evaluate(&self, _interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        Err(self.report_error(
+            &self.callee,
+            format!(
+                "'draw.{}' can only be evaluated in game.",
+                self.callee.lexeme
+            ),
+        ))
+    }
+}
+
+impl Interpretable for Global {
+    fn evaluate(&self, _interpreter: &mut Interpreter) -> Result<Ob, EvaluationError> {
+        if let Some(value) = builtins::math_constant(&self.name.lexeme) {
+            Ok(Ob::Number(value))
+        } else if builtins::is_content(&self.name.lexeme) {
+            Ok(Ob::Content(self.name.lexeme.to_owned()))
+        } else {
+            Err(self.report_error(
+                &self.name,
+                format!("'{}' can only be evaluated in game.", self.name.lexeme),
+            ))
+        }
+    }
+}
+
+impl Executable for Stmt {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        self.0.borrow().execute(interpreter)
+    }
+}
+
+impl Executable for Expression {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        self.expression.evaluate(interpreter)?;
+        Ok(())
+    }
+}
+
+impl Executable for Var {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        let value = match &self.initializer {
+            Some(initializer) => initializer.evaluate(interpreter)?,
+            None => Ob::Null,
+        };
+        interpreter.environment.define(&self.name.lexeme, value);
+        Ok(())
+    }
+}
+
+impl Executable for Array {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        let block = &self.block.lexeme;
+        if !interpreter.links.contains(block) && interpreter.environment.get(block).is_none() {
+            return Err(
+                self.report_error(&self.block, format!("Undefined memory block '{block}'."))
+            );
+        }
+        // Running the same declaration again keeps its slots.
+        if let Some(slots) = interpreter.arrays.get(&self.name.span) {
+            let slots = slots.to_owned();
+            interpreter
+                .environment
+                .define_array(&self.name.lexeme, slots);
+            return Ok(());
+        }
+
+        let offset = interpreter
+            .arrays
+            .values()
+            .filter(|slots| slots.block == *block)
+            .map(|slots| slots.offset + slots.size)
+            .max()
+            .unwrap_or(0);
+        let used = offset + self.size;
+        if let Some(capacity) = builtins::memory_capacity(block) {
+            if used > capacity {
+                return Err(self.report_error(
+                    &self.block,
+                    format!("Arrays in '{block}' need {used} slots but it only holds {capacity}."),
+                ));
+            }
+        }
+
+        let memory = interpreter.memory.entry(block.to_owned()).or_default();
+        if memory.len() < used {
+            memory.resize(used, 0.);
+        }
+        let slots = Slots {
+            block: block.to_owned(),
+            offset,
+            size: self.size,
+        };
+        interpreter.arrays.insert(self.name.span, slots.to_owned());
+        interpreter
+            .environment
+            .define_array(&self.name.lexeme, slots);
+        Ok(())
+    }
+}
+
+impl Executable for Destructure {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        // Only in-game calls return several values, so any value that does
+        // evaluate here is a single one.
+        self.initializer.evaluate(interpreter)?;
+        Err(self.report_error(
+            &self.paren,
+            format!("Expected {} values but got 1.", self.names.len()),
+        ))
+    }
+}
+
+impl Executable for Block {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        interpreter.environment.push_scope();
+        let mut result = Ok(());
+        for statement in &self.statements {
+            result = statement.execute(interpreter);
+            if result.is_err() || interpreter.flow.is_some() {
+                break;
+            }
+        }
+        interpreter.environment.pop_scope();
+        result
+    }
+}
+
+impl Executable for If {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        match self.condition.evaluate(interpreter)? {
+            Ob::Boolean(true) => self.then_branch.execute(interpreter),
+            Ob::Boolean(false) => match &self.else_branch {
+                Some(else_branch) => else_branch.execute(interpreter),
+                None => Ok(()),
+            },
+            condition => Err(condition_error(&self.keyword, &*self.condition, &condition)),
+        }
+    }
+}
+
+impl Executable for While {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        loop {
+            if let Some(condition) = &self.condition {
+                match condition.evaluate(interpreter)? {
+                    Ob::Boolean(true) => {}
+                    Ob::Boolean(false) => return Ok(()),
+                    value => return Err(condition_error(&self.keyword, &**condition, &value)),
+                }
+            }
+
+            self.body.execute(interpreter)?;
+            if interpreter.end_iteration(&self.label) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Executable for For {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        interpreter.environment.push_scope();
+        let result = self.run(interpreter);
+        interpreter.environment.pop_scope();
+        result
+    }
+}
+
+impl For {
+    fn run(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        if let Some(initializer) = &self.initializer {
+            initializer.execute(interpreter)?;
+        }
+
+        loop {
+            if let Some(condition) = &self.condition {
+                match condition.evaluate(interpreter)? {
+                    Ob::Boolean(true) => {}
+                    Ob::Boolean(false) => return Ok(()),
+                    value => return Err(condition_error(&self.keyword, &**condition, &value)),
+                }
+            }
+
+            self.body.execute(interpreter)?;
+            if interpreter.end_iteration(&self.label) {
+                return Ok(());
+            }
+
+            if let Some(increment) = &self.increment {
+                increment.evaluate(interpreter)?;
+            }
+        }
+    }
+}
+
+impl Executable for Jump {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        let label = self.label.as_ref().map(|label| label.lexeme.to_owned());
+        interpreter.flow = Some(match self.keyword.ttype {
+            TT::Break => Flow::Break(label),
+            _ => Flow::Continue(label),
+        });
+        Ok(())
+    }
+}
+
+impl Executable for Link {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        interpreter
+            .links
+            .extend(self.names.iter().map(|name| name.lexeme.to_owned()));
+        Ok(())
+    }
+}
+
+impl Executable for Function {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        interpreter.functions.insert(
+            self.name.lexeme.to_owned(),
+            Callable {
+                params: self.params.clone(),
+                body: self.body.clone(),
+                globals: interpreter.environment.globals(),
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Executable for Return {
+    fn execute(&self, interpreter: &mut Interpreter) -> Result<(), EvaluationError> {
+        let value = match &self.value {
+            Some(value) => value.evaluate(interpreter)?,
+            None => Ob::Null,
+        };
+        interpreter.flow = Some(Flow::Return(value));
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::token::Object as Ob;
+
+use super::Slots;
+
+/// What a name in scope stands for.
+enum Binding {
+    Value(Ob),
+    Array(Slots),
+}
+
+pub struct Environment {
+    scopes: Vec<HashMap<String, Binding>>,
+}
+
+/// Bindings a function can't see, put back by `exit_function`.
+pub struct Hidden {
+    scopes: Vec<HashMap<String, Binding>>,
+    globals: HashMap<String, Binding>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// The names declared in the global scope so far.
+    pub fn globals(&self) -> HashSet<String> {
+        self.scopes[0].keys().cloned().collect()
+    }
+
+    /// Hides every scope but the global one behind a fresh function scope,
+    /// along with the globals not in `visible`, returning them for
+    /// `exit_function`.
+    pub fn enter_function(&mut self, visible: &HashSet<String>) -> Hidden {
+        let scopes = self.scopes.split_off(1);
+        let globals = self.scopes[0]
+            .extract_if(|name, _| !visible.contains(name))
+            .collect();
+        self.push_scope();
+        Hidden { scopes, globals }
+    }
+
+    pub fn exit_function(&mut self, hidden: Hidden) {
+        self.scopes.truncate(1);
+        self.scopes[0].extend(hidden.globals);
+        self.scopes.extend(hidden.scopes);
+    }
+
+    pub fn define(&mut self, name: &str, value: Ob) {
+        self.bind(name, Binding::Value(value));
+    }
+
+    pub fn define_array(&mut self, name: &str, slots: Slots) {
+        self.bind(name, Binding::Array(slots));
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        self.scopes
+            .last_mut()
+            .expect("environment always has a global scope")
+            .insert(name.to_owned(), binding);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The value of the variable `name`, unless an array shadows it.
+    pub fn get(&self, name: &str) -> Option<Ob> {
+        match self.lookup(name)? {
+            Binding::Value(value) => Some(value.to_owned()),
+            Binding::Array(_) => None,
+        }
+    }
+
+    /// Where the elements of the array `name` are, unless a variable
+    /// shadows it.
+    pub fn array(&self, name: &str) -> Option<&Slots> {
+        match self.lookup(name)? {
+            Binding::Array(slots) => Some(slots),
+            Binding::Value(_) => None,
+        }
+    }
+
+    /// Overwrites the innermost binding of `name`, returning `false` if it was
+    /// never declared or is an array.
+    pub fn assign(&mut self, name: &str, value: Ob) -> bool {
+        match self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+        {
+            Some(Binding::Value(slot)) => {
+                *slot = value;
+                true
+            }
+            _ => false,
+        }
+    }
+}
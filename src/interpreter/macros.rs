@@ -19,6 +19,38 @@ macro_rules! un_err_msg {
     };
 }
 
+macro_rules! call_err_msg {
+    ($callee: expr, $arguments: expr) => {
+        format!(
+            "Argument types {} are invalid for '{}'.",
+            $arguments
+                .iter()
+                .map(|argument| format!("`{}`", argument.dtype()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            $callee.lexeme,
+        )
+    };
+}
+
+macro_rules! branch_err_msg {
+    ($then_type: expr, $else_type: expr) => {
+        format!(
+            "Conditional branch types `{}` and `{}` are incompatible.",
+            $then_type, $else_type,
+        )
+    };
+}
+
+macro_rules! cond_err_msg {
+    ($operand: expr) => {
+        format!(
+            "Condition type `{}` is invalid, expected `Boolean`.",
+            $operand.dtype(),
+        )
+    };
+}
+
 macro_rules! bin_match {
     ($self:ident, $left:ident, $right: ident, $op:tt, {[$a1:tt, $b1:tt, $c1:tt]$(, [$a2:tt, $b2:tt, $c2:tt])* $(, )?}$(, {$([$pat:pat, $e:expr], )*})? ) => {
         match (&$left, &$right) {
@@ -29,10 +61,7 @@ macro_rules! bin_match {
             $($(
                 $pat => $e,
             )*)?
-            _ => Err($self.report_error(
-                &$self.operator,
-                bin_err_msg!($self.operator, $left, $right),
-            )),
+            _ => Err($self.operand_error(&$left, &$right)),
         }
     };
 }
@@ -47,10 +76,7 @@ macro_rules! bin_match_deref {
             $($(
                 $pat => $e,
             )*)?
-            _ => Err($self.report_error(
-                &$self.operator,
-                bin_err_msg!($self.operator, $left, $right),
-            )),
+            _ => Err($self.operand_error(&$left, &$right)),
         }
     };
 }
@@ -58,13 +84,10 @@ macro_rules! bin_match_deref {
 macro_rules! bin_match_iuf {
     ($self:ident, $left:ident, $right: ident, $op:tt) => {
         match (&$left, &$right) {
-            (Number(val_left), Number(val_right)) => Ok(Number(
-                ((*val_left as i32).$op(*val_right as u32)) as f32,
-            )),
-            _ => Err($self.report_error(
-                &$self.operator,
-                bin_err_msg!($self.operator, $left, $right),
-            )),
+            (Number(val_left), Number(val_right)) => {
+                Ok(Number(((*val_left as i32).$op(*val_right as u32)) as f32))
+            }
+            _ => Err($self.operand_error(&$left, &$right)),
         }
     };
 }
@@ -72,23 +95,21 @@ macro_rules! bin_match_iuf {
 macro_rules! bin_match_iif {
     ($self:ident, $left:ident, $right: ident, $op:tt) => {
         match (&$left, &$right) {
-            (Number(val_left), Number(val_right)) => Ok(Number(
-                ((*val_left as i32).$op(*val_right as i32)) as f32,
-            )),
-            _ => Err($self.report_error(
-                &$self.operator,
-                bin_err_msg!($self.operator, $left, $right),
-            )),
+            (Number(val_left), Number(val_right)) => {
+                Ok(Number(((*val_left as i32).$op(*val_right as i32)) as f32))
+            }
+            _ => Err($self.operand_error(&$left, &$right)),
         }
     };
 }
 
+#[allow(unused_macros)]
 macro_rules! vec2d {
     [ $( [ $( $d:expr ),* ] ),* ] => {
         vec![
             $(
                 vec![$($d),*],
-            )* 
+            )*
         ]
     }
-}
\ No newline at end of file
+}
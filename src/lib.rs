@@ -1,76 +1,83 @@
-use std::{error::Error, fs};
-
-use error::EvaluationError;
-use error::ParseError;
+use codegen::Compiler;
+use error::Diagnostic;
+use interpreter::Interpreter;
 use parser::Parser;
 use scanner::Scanner;
+use stmt::Stmt;
 use token::Token;
-use token::TokenType as TT;
-
-use crate::interpreter::Interpretable;
 
+pub mod builtins;
+pub mod codegen;
 pub mod error;
 pub mod expr;
 pub mod interpreter;
+pub mod optimiser;
 pub mod parser;
+pub mod report;
 pub mod scanner;
+pub mod stmt;
 pub mod token;
 
-pub fn run_file(filepath: &str) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read(filepath)?;
-
-    let _ = run(contents);
-
-    Ok(())
+/// A compiled program.
+pub struct Output {
+    pub code: String,
+    /// Warnings that didn't stop the program from compiling.
+    pub warnings: Vec<Diagnostic>,
 }
 
-pub fn run(source: Vec<u8>) -> Result<(), ParseError> {
+pub fn tokens(source: Vec<u8>) -> Result<Vec<Token>, Vec<Diagnostic>> {
     let mut scanner = Scanner::new(source);
-    let tokens: Vec<Token> = scanner.scan_tokens();
-    eprintln!("scanned");
-    let mut parser = Parser::new(tokens);
-    let expression = parser.parse()?;
-    eprintln!("parsed");
-
-    println!("{}", expression);
-
-    println!("{:?}", expression.evaluate());
-
-    Ok(())
+    let tokens = scanner.scan_tokens();
+    match scanner.errors() {
+        [] => Ok(tokens),
+        errors => Err(errors.to_vec()),
+    }
 }
 
-pub fn error(ln: usize, col: usize, message: String) {
-    report(ln, col, "", message.as_str())
+/// Parses `source`, failing if scanning or parsing found any error. Parsing
+/// carries on past scan errors so that all of them are reported at once.
+pub fn parse(source: Vec<u8>) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    let mut diagnostics = scanner.errors().to_vec();
 
-    // TODO: had_error global variable (need to figure it out)
+    match Parser::new(tokens).parse() {
+        Ok(statements) if diagnostics.is_empty() => Ok(statements),
+        Ok(_) => Err(diagnostics),
+        Err(errors) => {
+            diagnostics.extend(errors.into_iter().map(Diagnostic::from));
+            Err(diagnostics)
+        }
+    }
 }
 
-pub fn parse_error(error: &ParseError) {
-    let ln = error.token.position.ln;
-    let col = error.token.position.col;
-    if error.token.ttype == TT::EOF {
-        report(ln, col, " at end", error.message.as_str());
-    } else {
-        report(
-            ln,
-            col,
-            format!(" at '{}'", error.token.lexeme).as_str(),
-            error.message.as_str(),
-        )
-    }
+pub fn compile(source: Vec<u8>) -> Result<Output, Vec<Diagnostic>> {
+    compile_with(source, Compiler::new())
 }
 
-pub fn runtime_error(error: &EvaluationError) {
-    let ln = error.token.position.ln;
-    let col = error.token.position.col;
-    report(
-        ln,
-        col,
-        format!(" at '{}'", error.token.lexeme).as_str(),
-        error.message.as_str(),
-    )
+/// Compiles `source` with a compiler set up with `with_stack` or
+/// `with_target`.
+pub fn compile_with(source: Vec<u8>, mut compiler: Compiler) -> Result<Output, Vec<Diagnostic>> {
+    let statements = parse(source)?;
+    optimiser::fold_program(&statements).map_err(|error| vec![error.into()])?;
+    let code = compiler
+        .compile_program(&statements)
+        .map_err(|error| vec![error.into()])?;
+
+    Ok(Output {
+        code,
+        warnings: compiler.warnings().to_vec(),
+    })
 }
 
-fn report(ln: usize, col: usize, lexeme: &str, message: &str) {
-    eprintln!("[ln {ln}, col {col}] Error{lexeme}: {message}")
+/// Runs `source` with the interpreter, returning the text it flushed with
+/// `printflush`.
+pub fn run(source: Vec<u8>) -> Result<String, Vec<Diagnostic>> {
+    let statements = parse(source)?;
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret(&statements)
+        .map_err(|error| vec![error.into()])?;
+
+    Ok(interpreter.output)
 }
@@ -1,34 +1,260 @@
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read},
+    process::ExitCode,
+};
+
 use mindustry_transpiler::{
-    expr::*,
-    run,
-    token::{Object, Position, Token, TokenType as TT},
+    codegen::{Compiler, StackConfig, Target},
+    error::{Diagnostic, Stage},
+    interpreter::Interpreter,
+    report,
+    token::Token,
 };
 
-fn main() {
-    let source = r#"3**--4**1-"#.to_owned();
+const USAGE: &str = "\
+Usage: mindustry-transpiler <command> [file] [options]
+
+Reads the script from standard input when no file (or '-') is given.
+
+Commands:
+    compile    Compile to mlog
+    run        Run with the interpreter
+    check      Report errors without running or emitting anything
+    tokens     Print the scanned tokens
+    ast        Print the parsed syntax tree
+
+Options for compile (and check, except -o):
+    -o <file>              Write the mlog to <file> instead of standard output
+    --target <v7|v8>       Mindustry version to compile for (default v7)
+    --stack <block:size>   Use <size> slots of <block> as a call stack";
+
+/// Why the command failed, which decides the exit code.
+enum Failure {
+    Usage(String),
+    Io(String),
+    Diagnostics(Vec<Diagnostic>),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Usage(_) | Failure::Io(_) => 1,
+            Failure::Diagnostics(diagnostics) => match diagnostics.first().map(|d| d.stage) {
+                Some(Stage::Scan) => 2,
+                Some(Stage::Parse) => 3,
+                Some(Stage::Evaluation) => 4,
+                Some(Stage::Compile) | None => 5,
+            },
+        }
+    }
+}
 
-    run(source.into_bytes());
+struct Options {
+    command: String,
+    input: Option<String>,
+    output: Option<String>,
+    target: Target,
+    stack: Option<StackConfig>,
+}
 
-    // Expr expression = new Expr.Binary(
-    //     new Expr.Unary(
-    //         new Token(TokenType.MINUS, "-", null, 1),
-    //         new Expr.Literal(123)),
-    //     new Token(TokenType.STAR, "*", null, 1),
-    //     new Expr.Grouping(
-    //         new Expr.Literal(45.67)));
+fn main() -> ExitCode {
+    match parse_args(env::args().skip(1)).and_then(|options| execute(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            match &failure {
+                Failure::Usage(message) => eprintln!("{message}\n\n{USAGE}"),
+                Failure::Io(message) => eprintln!("{message}"),
+                // Already shown by `execute`, which has the source to quote.
+                Failure::Diagnostics(_) => {}
+            }
+            ExitCode::from(failure.exit_code())
+        }
+    }
+}
 
-    let ex = Unary::new(
-        Token::new(TT::Minus, "-".to_owned(), None, Position::new(1, 1)),
-        Literal {
-            value: Object::Number(123.),
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, Failure> {
+    let Some(command) = args.next() else {
+        return Err(Failure::Usage("Expected a command.".to_owned()));
+    };
+    if !["compile", "run", "check", "tokens", "ast"].contains(&command.as_str()) {
+        return Err(Failure::Usage(format!("Unknown command '{command}'.")));
+    }
+
+    let mut options = Options {
+        command,
+        input: None,
+        output: None,
+        target: Target::default(),
+        stack: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| Failure::Usage(format!("Expected a value after '{name}'.")))
+        };
+        match arg.as_str() {
+            "-o" => options.output = Some(value("-o")?),
+            "--target" => {
+                options.target = match value("--target")?.as_str() {
+                    "v7" => Target::V7,
+                    "v8" => Target::V8,
+                    target => return Err(Failure::Usage(format!("Unknown target '{target}'."))),
+                }
+            }
+            "--stack" => {
+                let stack = value("--stack")?;
+                let config = stack.split_once(':').and_then(|(block, size)| {
+                    Some(StackConfig {
+                        block: block.to_owned(),
+                        size: size.parse().ok()?,
+                    })
+                });
+                match config {
+                    Some(config) => options.stack = Some(config),
+                    None => {
+                        return Err(Failure::Usage(format!(
+                            "Expected '<block>:<size>' after '--stack' but got '{stack}'."
+                        )))
+                    }
+                }
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(Failure::Usage(format!("Unknown option '{arg}'.")))
+            }
+            _ if options.input.is_none() => options.input = Some(arg),
+            _ => return Err(Failure::Usage(format!("Unexpected argument '{arg}'."))),
+        }
+    }
+
+    if options.command != "compile" && options.output.is_some() {
+        return Err(Failure::Usage(format!(
+            "'-o' only applies to 'compile', not '{}'.",
+            options.command
+        )));
+    }
+    if !["compile", "check"].contains(&options.command.as_str()) && options.stack.is_some() {
+        return Err(Failure::Usage(format!(
+            "'--stack' only applies to 'compile' and 'check', not '{}'.",
+            options.command
+        )));
+    }
+    Ok(options)
+}
+
+/// Shows diagnostics with the lines of the script they point at.
+struct Reporter {
+    name: String,
+    source: String,
+    colour: bool,
+}
+
+impl Reporter {
+    fn report(&self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            eprintln!(
+                "{}",
+                report::render(diagnostic, &self.name, &self.source, self.colour)
+            );
+        }
+    }
+}
+
+fn execute(options: &Options) -> Result<(), Failure> {
+    let source = read_source(options.input.as_deref())?;
+    let reporter = Reporter {
+        name: match options.input.as_deref() {
+            Some(path) if path != "-" => path.to_owned(),
+            _ => "<stdin>".to_owned(),
         },
-    );
+        source: String::from_utf8_lossy(&source).into_owned(),
+        colour: io::stderr().is_terminal(),
+    };
+
+    let result = command(options, source, &reporter);
+    if let Err(Failure::Diagnostics(diagnostics)) = &result {
+        reporter.report(diagnostics);
+    }
+    result
+}
 
-    let bin = Binary::new(
-        Token::new(TT::Ast, "*".to_owned(), None, Position::new(1, 1)),
-        ex,
-        Grouping::new(Literal::new(Object::Degree(45.67))),
-    );
+fn command(options: &Options, source: Vec<u8>, reporter: &Reporter) -> Result<(), Failure> {
+    match options.command.as_str() {
+        "tokens" => {
+            for token in mindustry_transpiler::tokens(source).map_err(Failure::Diagnostics)? {
+                println!("{}", describe(&token));
+            }
+            Ok(())
+        }
+        "ast" => {
+            for statement in mindustry_transpiler::parse(source).map_err(Failure::Diagnostics)? {
+                println!("{statement}");
+            }
+            Ok(())
+        }
+        "run" => {
+            let statements = mindustry_transpiler::parse(source).map_err(Failure::Diagnostics)?;
+            let mut interpreter = Interpreter::new();
+            let result = interpreter.interpret(&statements);
+            // Show what was flushed before any error.
+            print!("{}", interpreter.output);
+            result.map_err(|error| Failure::Diagnostics(vec![error.into()]))
+        }
+        "check" => compile(source, options, reporter).map(|_| ()),
+        "compile" => {
+            let mut code = compile(source, options, reporter)?;
+            code.push('\n');
+            match &options.output {
+                Some(path) => fs::write(path, code)
+                    .map_err(|error| Failure::Io(format!("Could not write '{path}': {error}."))),
+                None => {
+                    print!("{code}");
+                    Ok(())
+                }
+            }
+        }
+        _ => unreachable!("commands are validated by parse_args"),
+    }
+}
+
+fn read_source(input: Option<&str>) -> Result<Vec<u8>, Failure> {
+    match input {
+        Some(path) if path != "-" => fs::read(path)
+            .map_err(|error| Failure::Io(format!("Could not read '{path}': {error}."))),
+        _ => {
+            let mut source = vec![];
+            io::stdin()
+                .read_to_end(&mut source)
+                .map_err(|error| Failure::Io(format!("Could not read standard input: {error}.")))?;
+            Ok(source)
+        }
+    }
+}
+
+fn compile(source: Vec<u8>, options: &Options, reporter: &Reporter) -> Result<String, Failure> {
+    let mut compiler = Compiler::new().with_target(options.target);
+    if let Some(stack) = &options.stack {
+        compiler = compiler
+            .with_stack(StackConfig {
+                block: stack.block.to_owned(),
+                size: stack.size,
+            })
+            .map_err(Failure::Usage)?;
+    }
+
+    let output =
+        mindustry_transpiler::compile_with(source, compiler).map_err(Failure::Diagnostics)?;
+    reporter.report(&output.warnings);
+    Ok(output.code)
+}
 
-    println!("{}", bin)
+fn describe(token: &Token) -> String {
+    let position = format!("{}:{}", token.span.ln, token.span.col);
+    match &token.literal {
+        Some(literal) => format!(
+            "{position:>8} {:?} '{}' {literal}",
+            token.ttype, token.lexeme
+        ),
+        None => format!("{position:>8} {:?} '{}'", token.ttype, token.lexeme),
+    }
 }
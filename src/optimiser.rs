@@ -0,0 +1,335 @@
+use crate::{
+    builtins,
+    error::CompileError,
+    expr::{
+        Assign, Binary, Builtin, Call, CompoundAssign, Conditional, Control, Draw, Expr, ExprLike,
+        Get, Global, Grouping, Index, Interpolation, Literal, Part, SetIndex, Unary, Variable,
+    },
+    interpreter::{Interpretable, Interpreter},
+    stmt::{
+        Array, Block, Destructure, Expression, For, Function, If, Jump, Link, Return, Stmt, Var,
+        While,
+    },
+    token::{Object as Ob, Token, TokenType as TT},
+};
+
+pub fn fold_program(statements: &[Stmt]) -> Result<(), CompileError> {
+    for statement in statements {
+        statement.clone().optimise()?;
+    }
+    Ok(())
+}
+
+pub trait Foldable {
+    /// Collapses constant subtrees into literals, returning this node's value
+    /// if the whole node is constant.
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError>;
+}
+
+pub trait Optimisable {
+    fn optimise(&mut self) -> Result<(), CompileError>;
+}
+
+fn fold_child(child: &mut Box<dyn ExprLike>) -> Result<Option<Ob>, CompileError> {
+    let value = child.fold()?;
+    if let Some(value) = &value {
+        *child = Box::new(Literal::new(value.to_owned(), child.span()));
+    }
+    Ok(value)
+}
+
+fn fold_optional(child: &mut Option<Box<dyn ExprLike>>) -> Result<(), CompileError> {
+    if let Some(child) = child {
+        fold_child(child)?;
+    }
+    Ok(())
+}
+
+/// Evaluates a node whose operands are all constant, rejecting results mlog
+/// has no way to write, such as the infinity from `1 / 0`.
+fn evaluate(node: &dyn Interpretable, token: &Token) -> Result<Option<Ob>, CompileError> {
+    let value = node.evaluate(&mut Interpreter::new())?;
+    match value {
+        Ob::Number(number) | Ob::Degree(number) if !number.is_finite() => Err(CompileError::new(
+            token,
+            format!("Constant expression evaluates to `{number}`, which mlog can't represent."),
+        )
+        .with_note("mlog stores it as `null` instead".to_owned())),
+        _ => Ok(Some(value)),
+    }
+}
+
+impl Foldable for Expr {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        self.0.borrow_mut().fold()
+    }
+}
+
+impl Foldable for Unary {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        match fold_child(&mut self.right)? {
+            Some(_) => evaluate(self, &self.operator),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Foldable for Binary {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        let left = fold_child(&mut self.left)?;
+        // The right operand of a decided `and`/`or` never runs, so it isn't
+        // folded either.
+        if let (TT::And, Some(Ob::Boolean(false))) | (TT::Or, Some(Ob::Boolean(true))) =
+            (self.operator.ttype, &left)
+        {
+            return evaluate(self, &self.operator);
+        }
+
+        let right = fold_child(&mut self.right)?;
+        match (left, right) {
+            (Some(_), Some(_)) => evaluate(self, &self.operator),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Foldable for Conditional {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        // Only the branch a constant condition takes is folded, so the other
+        // may hold anything the interpreter would never evaluate.
+        let taken = match fold_child(&mut self.condition)? {
+            Some(Ob::Boolean(true)) => fold_child(&mut self.then_branch)?,
+            Some(Ob::Boolean(false)) => fold_child(&mut self.else_branch)?,
+            _ => {
+                fold_child(&mut self.then_branch)?;
+                fold_child(&mut self.else_branch)?;
+                None
+            }
+        };
+        match taken {
+            Some(_) => evaluate(self, &self.keyword),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Foldable for Grouping {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.expression)
+    }
+}
+
+impl Foldable for Literal {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        Ok(Some(self.value.to_owned()))
+    }
+}
+
+impl Foldable for Variable {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        Ok(None)
+    }
+}
+
+impl Foldable for Interpolation {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        let mut constant = true;
+        for part in &mut self.parts {
+            if let Part::Value(value) = part {
+                constant &= fold_child(value)?.is_some();
+            }
+        }
+
+        if constant {
+            evaluate(self, &self.string)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Foldable for Index {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.index)?;
+        Ok(None)
+    }
+}
+
+impl Foldable for SetIndex {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.index)?;
+        fold_child(&mut self.value)?;
+        Ok(None)
+    }
+}
+
+impl Foldable for Assign {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.value)?;
+        Ok(None)
+    }
+}
+
+impl Foldable for CompoundAssign {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.operation.right)?;
+        Ok(None)
+    }
+}
+
+impl Foldable for Call {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        for argument in &mut self.arguments {
+            fold_child(argument)?;
+        }
+        Ok(None)
+    }
+}
+
+impl Foldable for Builtin {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        let mut constant = builtins::lookup(&self.callee.lexeme).is_some_and(|f| f.pure);
+        for argument in &mut self.arguments {
+            constant &= fold_child(argument)?.is_some();
+        }
+
+        if constant {
+            evaluate(self, &self.callee)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Foldable for Get {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        match fold_child(&mut self.object)? {
+            Some(_) => evaluate(self, &self.name),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Foldable for Control {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        fold_child(&mut self.object)?;
+        for argument in &mut self.arguments {
+            fold_child(argument)?;
+        }
+        Ok(None)
+    }
+}
+
+impl Foldable for Draw {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        for argument in &mut self.arguments {
+            fold_child(argument)?;
+        }
+        Ok(None)
+    }
+}
+
+impl Foldable for Global {
+    fn fold(&mut self) -> Result<Option<Ob>, CompileError> {
+        // Math constants are built into mlog, so they're left for the
+        // processor rather than written out as a rounded literal.
+        if builtins::VARIABLES.contains(&self.name.lexeme.as_str())
+            || builtins::math_constant(&self.name.lexeme).is_some()
+        {
+            Ok(None)
+        } else {
+            evaluate(self, &self.name)
+        }
+    }
+}
+
+impl Optimisable for Stmt {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        self.0.borrow_mut().optimise()
+    }
+}
+
+impl Optimisable for Expression {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_child(&mut self.expression)?;
+        Ok(())
+    }
+}
+
+impl Optimisable for Var {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_optional(&mut self.initializer)
+    }
+}
+
+impl Optimisable for Array {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        Ok(())
+    }
+}
+
+impl Optimisable for Destructure {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_child(&mut self.initializer)?;
+        Ok(())
+    }
+}
+
+impl Optimisable for Block {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_program(&self.statements)
+    }
+}
+
+impl Optimisable for If {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_child(&mut self.condition)?;
+        self.then_branch.optimise()?;
+        if let Some(else_branch) = &mut self.else_branch {
+            else_branch.optimise()?;
+        }
+        Ok(())
+    }
+}
+
+impl Optimisable for While {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_optional(&mut self.condition)?;
+        self.body.optimise()
+    }
+}
+
+impl Optimisable for For {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        if let Some(initializer) = &mut self.initializer {
+            initializer.optimise()?;
+        }
+        fold_optional(&mut self.condition)?;
+        fold_optional(&mut self.increment)?;
+        self.body.optimise()
+    }
+}
+
+impl Optimisable for Link {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        Ok(())
+    }
+}
+
+impl Optimisable for Jump {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        Ok(())
+    }
+}
+
+impl Optimisable for Function {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_program(&self.body)
+    }
+}
+
+impl Optimisable for Return {
+    fn optimise(&mut self) -> Result<(), CompileError> {
+        fold_optional(&mut self.value)
+    }
+}
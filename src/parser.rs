@@ -1,10 +1,10 @@
-use std::{error::Error, fmt::Display};
-
 use crate::{
+    builtins,
     error::ParseError,
     expr::*,
+    scanner::Scanner,
+    stmt::*,
     token::{TokenType as TT, *},
-    parse_error,
 };
 
 #[macro_use]
@@ -14,6 +14,9 @@ mod macros;
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    loops: Vec<Option<String>>,
+    in_function: bool,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
@@ -24,12 +27,484 @@ impl Parser {
         }
     }
 
-    pub fn parse(&mut self) -> Result<Expr, ParseError> {
-        return self.expression();
+    /// Parses the whole program, recovering from errors at statement
+    /// boundaries so every one of them is reported.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut statements = vec![];
+        while !self.is_at_end() {
+            // `synchronise` stops before a '}', but at the top level there's
+            // no block for it to close.
+            if self.check(TT::RBrace) {
+                let error = self.report_error(self.peek(), "Unmatched '}'.");
+                self.errors.push(error);
+                self.advance();
+                continue;
+            }
+
+            let statement = if self.cond_advance(vec![TT::Fun]) {
+                self.recover(Self::function)
+            } else if self.cond_advance(vec![TT::Link]) {
+                self.recover(Self::link_declaration)
+            } else {
+                self.recover(Self::declaration)
+            };
+            statements.extend(statement);
+        }
+
+        // An error token was already reported by the scanner, so whatever
+        // the parser expected in its place would only repeat that error.
+        self.errors.retain(|error| error.token.ttype != TT::Error);
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Runs `rule`, recording its error and skipping to the next statement
+    /// if it fails.
+    fn recover(&mut self, rule: fn(&mut Self) -> Result<Stmt, ParseError>) -> Option<Stmt> {
+        match rule(self) {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronise();
+                None
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        if self.cond_advance(vec![TT::Var]) {
+            self.var_declaration()
+        } else if self.check(TT::Fun) {
+            Err(self.report_error(
+                self.peek(),
+                "Functions can only be declared at the top level.",
+            ))
+        } else if self.check(TT::Link) {
+            Err(self.report_error(self.peek(), "Links can only be declared at the top level."))
+        } else {
+            self.statement()
+        }
+    }
+
+    fn function(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.previous().span;
+        let name = self
+            .consume(TT::Identifier, "Expected function name.")?
+            .to_owned();
+        if builtins::lookup(&name.lexeme).is_some() {
+            let message = format!("Cannot redeclare built-in function '{}'.", name.lexeme);
+            return Err(self.report_error(&name, message.as_str()));
+        }
+        self.consume(TT::LParen, "Expected '(' after function name.")?;
+
+        let mut params = vec![];
+        if !self.check(TT::RParen) {
+            loop {
+                let param = self.consume(TT::Identifier, "Expected parameter name.")?;
+                if params
+                    .iter()
+                    .any(|other: &Token| other.lexeme == param.lexeme)
+                {
+                    let message = format!("Duplicate parameter '{}'.", param.lexeme);
+                    return Err(self.report_error(self.previous(), message.as_str()));
+                }
+                params.push(param.to_owned());
+
+                if !self.cond_advance(vec![TT::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TT::RParen, "Expected ')' after parameters.")?;
+        self.consume(TT::LBrace, "Expected '{' before function body.")?;
+
+        self.in_function = true;
+        let body = self.block();
+        self.in_function = false;
+
+        Ok(Function::new(name, params, body?, self.span_from(start)).into_stmt())
+    }
+
+    fn link_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().to_owned();
+        let mut names = vec![];
+        loop {
+            let name = self
+                .consume(TT::Identifier, "Expected building name.")?
+                .to_owned();
+            if !builtins::is_link(&name.lexeme) {
+                let message = format!("'{}' is not the name of a linked building.", name.lexeme);
+                return Err(self
+                    .report_error(&name, message.as_str())
+                    .with_help("links are named after their block, like `cell1`".to_owned()));
+            }
+            names.push(name);
+
+            if !self.cond_advance(vec![TT::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TT::Semicolon, "Expected ';' after links.")?;
+        let span = self.span_from(keyword.span);
+        Ok(Link::new(keyword, names, span).into_stmt())
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.previous().span;
+        if self.cond_advance(vec![TT::LParen]) {
+            return self.destructure(start);
+        }
+
+        let name = self
+            .consume(TT::Identifier, "Expected variable name.")?
+            .to_owned();
+        if self.cond_advance(vec![TT::Colon]) {
+            return self.array_declaration(start, name);
+        }
+
+        let initializer = if self.cond_advance(vec![TT::Equals]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume(TT::Semicolon, "Expected ';' after variable declaration.")?;
+        Ok(Var::new(name, initializer, self.span_from(start)).into_stmt())
+    }
+
+    fn array_declaration(&mut self, start: Span, name: Token) -> Result<Stmt, ParseError> {
+        self.consume(TT::LBracket, "Expected '[' before array size.")?;
+        let size = self.consume(TT::Number, "Expected array size.")?.to_owned();
+        let size = match size.literal {
+            Some(Object::Number(value)) if value >= 1. && value.fract() == 0. => value as usize,
+            _ => return Err(self.report_error(&size, "Array size must be a positive integer.")),
+        };
+        self.consume(TT::RBracket, "Expected ']' after array size.")?;
+        self.consume(TT::In, "Expected 'in' after array size.")?;
+        let block = self
+            .consume(TT::Identifier, "Expected memory block after 'in'.")?
+            .to_owned();
+        self.consume(TT::Semicolon, "Expected ';' after variable declaration.")?;
+        Ok(Array::new(name, size, block, self.span_from(start)).into_stmt())
+    }
+
+    fn destructure(&mut self, start: Span) -> Result<Stmt, ParseError> {
+        let paren = self.previous().to_owned();
+        let mut names = vec![];
+        loop {
+            let name = self
+                .consume(TT::Identifier, "Expected variable name.")?
+                .to_owned();
+            if names
+                .iter()
+                .any(|other: &Token| other.lexeme == name.lexeme)
+            {
+                let message = format!("Duplicate variable '{}'.", name.lexeme);
+                return Err(self.report_error(&name, message.as_str()));
+            }
+            names.push(name);
+            if !self.cond_advance(vec![TT::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TT::RParen, "Expected ')' after variable names.")?;
+        self.consume(TT::Equals, "Expected '=' after variable names.")?;
+        let initializer = self.expression()?;
+        self.consume(TT::Semicolon, "Expected ';' after variable declaration.")?;
+        Ok(Destructure::new(paren, names, initializer, self.span_from(start)).into_stmt())
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.check(TT::Identifier) && self.peek_n(1).ttype == TT::Colon {
+            let label = self.advance().to_owned();
+            self.advance();
+            if self.cond_advance(vec![TT::While, TT::For, TT::Loop]) {
+                self.loop_statement(Some(label))
+            } else {
+                Err(self.report_error(self.peek(), "Expected loop after label."))
+            }
+        } else if self.cond_advance(vec![TT::While, TT::For, TT::Loop]) {
+            self.loop_statement(None)
+        } else if self.cond_advance(vec![TT::Break, TT::Continue]) {
+            self.jump_statement()
+        } else if self.cond_advance(vec![TT::Return]) {
+            self.return_statement()
+        } else if self.cond_advance(vec![TT::If]) {
+            self.if_statement()
+        } else if self.cond_advance(vec![TT::LBrace]) {
+            self.block_statement()
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().to_owned();
+        let condition = self.expression()?;
+
+        self.consume(TT::LBrace, "Expected '{' after if condition.")?;
+        let then_branch = self.block_statement()?;
+
+        let else_branch = if self.cond_advance(vec![TT::Else]) {
+            if self.cond_advance(vec![TT::If]) {
+                Some(self.if_statement()?)
+            } else {
+                self.consume(TT::LBrace, "Expected '{' after 'else'.")?;
+                Some(self.block_statement()?)
+            }
+        } else {
+            None
+        };
+
+        let span = self.span_from(keyword.span);
+        Ok(If::new(keyword, condition, then_branch, else_branch, span).into_stmt())
+    }
+
+    fn loop_statement(&mut self, label: Option<Token>) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().to_owned();
+
+        let mut initializer = None;
+        let mut condition = None;
+        let mut increment = None;
+        match keyword.ttype {
+            TT::While => condition = Some(self.expression()?),
+            TT::For => {
+                initializer = if self.cond_advance(vec![TT::Semicolon]) {
+                    None
+                } else if self.cond_advance(vec![TT::Var]) {
+                    Some(self.var_declaration()?)
+                } else {
+                    Some(self.expression_statement()?)
+                };
+                if !self.check(TT::Semicolon) {
+                    condition = Some(self.expression()?);
+                }
+                self.consume(TT::Semicolon, "Expected ';' after loop condition.")?;
+                if !self.check(TT::LBrace) {
+                    increment = Some(self.expression()?);
+                }
+            }
+            _ => {}
+        }
+
+        self.consume(
+            TT::LBrace,
+            format!("Expected '{{' after '{}' header.", keyword.lexeme).as_str(),
+        )?;
+        self.loops
+            .push(label.as_ref().map(|label| label.lexeme.to_owned()));
+        let body = self.block_statement();
+        self.loops.pop();
+        let body = body?;
+
+        let start = label.as_ref().unwrap_or(&keyword).span;
+        let span = self.span_from(start);
+        if keyword.ttype == TT::For {
+            Ok(For::new(
+                label,
+                keyword,
+                initializer,
+                condition,
+                increment,
+                body,
+                span,
+            )
+            .into_stmt())
+        } else {
+            Ok(While::new(label, keyword, condition, body, span).into_stmt())
+        }
+    }
+
+    fn jump_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().to_owned();
+        let label = if self.check(TT::Identifier) {
+            Some(self.advance().to_owned())
+        } else {
+            None
+        };
+
+        match &label {
+            _ if self.loops.is_empty() => {
+                return Err(self.report_error(
+                    &keyword,
+                    format!("Cannot '{}' outside of a loop.", keyword.lexeme).as_str(),
+                ))
+            }
+            Some(label) if !self.loops.contains(&Some(label.lexeme.to_owned())) => {
+                return Err(self.report_error(
+                    label,
+                    format!("Undeclared loop label '{}'.", label.lexeme).as_str(),
+                ))
+            }
+            _ => {}
+        }
+
+        self.consume(
+            TT::Semicolon,
+            format!("Expected ';' after '{}'.", keyword.lexeme).as_str(),
+        )?;
+        let span = self.span_from(keyword.span);
+        Ok(Jump::new(keyword, label, span).into_stmt())
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().to_owned();
+        if !self.in_function {
+            return Err(self.report_error(&keyword, "Cannot 'return' outside of a function."));
+        }
+
+        let value = if self.check(TT::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume(TT::Semicolon, "Expected ';' after return value.")?;
+        let span = self.span_from(keyword.span);
+        Ok(Return::new(keyword, value, span).into_stmt())
+    }
+
+    /// Parses a block after its opening brace into a `Block` statement.
+    fn block_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.previous().span;
+        let statements = self.block()?;
+        Ok(Block::new(statements, self.span_from(start)).into_stmt())
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = vec![];
+        while !self.check(TT::RBrace) && !self.is_at_end() {
+            statements.extend(self.recover(Self::declaration));
+        }
+
+        self.consume(TT::RBrace, "Expected '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.peek().span;
+        let expression = self.expression()?;
+        self.consume(TT::Semicolon, "Expected ';' after expression.")?;
+        Ok(Expression::new(expression, self.span_from(start)).into_stmt())
     }
 
     fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.logic_or()
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        if self.check(TT::Identifier) && self.peek_n(1).ttype == TT::Equals {
+            let name = self.advance().to_owned();
+            self.advance();
+            let value = self.assignment()?;
+            Ok(Assign::new(name, value).into_expr())
+        } else if let Some(ttype) = self
+            .check(TT::Identifier)
+            .then(|| compound_operator(self.peek_n(1).ttype))
+            .flatten()
+        {
+            let name = self.advance().to_owned();
+            let mut operator = self.advance().to_owned();
+            operator.ttype = ttype;
+            let value = self.assignment()?;
+            Ok(CompoundAssign::new(name, operator, value).into_expr())
+        } else if self.check(TT::Identifier) && self.is_increment(1) {
+            // `i++` and `i--` are `i += 1` and `i -= 1`.
+            let name = self.advance().to_owned();
+            let (operator, one) = self.increment();
+            Ok(CompoundAssign::new(name, operator, one).into_expr())
+        } else if self.check(TT::Identifier) && self.peek_n(1).ttype == TT::LBracket {
+            // Only an `=` after the closing bracket makes this an element
+            // assignment rather than a read.
+            let save = self.current;
+            let name = self.advance().to_owned();
+            let bracket = self.advance().to_owned();
+            let index = self.expression()?;
+            self.consume(TT::RBracket, "Expected ']' after index.")?;
+            if self.cond_advance(vec![TT::Equals]) {
+                let value = self.assignment()?;
+                Ok(SetIndex::new(name, bracket, index, value).into_expr())
+            } else if let Some(ttype) = compound_operator(self.peek().ttype) {
+                let mut operator = self.advance().to_owned();
+                operator.ttype = ttype;
+                let value = self.assignment()?;
+                Ok(SetIndex::new(name, bracket, index, value)
+                    .with_operator(operator)
+                    .into_expr())
+            } else if self.is_increment(0) {
+                let (operator, one) = self.increment();
+                Ok(SetIndex::new(name, bracket, index, one)
+                    .with_operator(operator)
+                    .into_expr())
+            } else {
+                self.current = save;
+                self.conditional()
+            }
+        } else {
+            self.conditional()
+        }
+    }
+
+    /// Whether the token `at` past the current one starts a `++` or `--`
+    /// ending the expression, as opposed to a sign like in `i - -1`.
+    fn is_increment(&self, at: usize) -> bool {
+        let first = self.peek_n(at);
+        if !matches!(first.ttype, TT::Plus | TT::Minus) {
+            return false;
+        }
+        let second = self.peek_n(at + 1);
+        second.ttype == first.ttype
+            && second.span.start == first.span.end
+            && matches!(
+                self.peek_n(at + 2).ttype,
+                TT::Semicolon
+                    | TT::RParen
+                    | TT::RBracket
+                    | TT::LBrace
+                    | TT::RBrace
+                    | TT::Comma
+                    | TT::Colon
+                    | TT::EOF
+            )
+    }
+
+    /// Consumes a `++` or `--`, returning it as one operator along with the
+    /// 1 it adds or subtracts.
+    fn increment(&mut self) -> (Token, Literal) {
+        let mut operator = self.advance().to_owned();
+        let second = self.advance();
+        operator.lexeme.push_str(&second.lexeme);
+        operator.span = operator.span.to(second.span);
+        let one = Literal::new(Object::Number(1.), second.span);
+        (operator, one)
+    }
+
+    fn conditional(&mut self) -> Result<Expr, ParseError> {
+        if self.cond_advance(vec![TT::If]) {
+            let keyword = self.previous().to_owned();
+            let condition = self.expression()?;
+            self.consume(TT::Then, "Expected 'then' after condition.")?;
+            let then_branch = self.expression()?;
+            self.consume(TT::Else, "Expected 'else' in conditional expression.")?;
+            let else_branch = self.conditional()?;
+            return Ok(Conditional::new(keyword, condition, then_branch, else_branch).into_expr());
+        }
+
+        let condition = self.logic_or()?;
+        if self.cond_advance(vec![TT::Question]) {
+            let keyword = self.previous().to_owned();
+            let then_branch = self.expression()?;
+            self.consume(TT::Colon, "Expected ':' in conditional expression.")?;
+            let else_branch = self.conditional()?;
+            Ok(Conditional::new(keyword, condition, then_branch, else_branch).into_expr())
+        } else {
+            Ok(condition)
+        }
     }
 
     binary!((logic_or, logic_and, [h TT::Or]));
@@ -54,7 +529,7 @@ impl Parser {
     }
 
     fn exponential(&mut self) -> Result<Expr, ParseError> {
-        let left = self.primary()?;
+        let left = self.postfix()?;
 
         if self.cond_advance(vec![TT::Ast2]) {
             let operator = self.previous().to_owned();
@@ -72,31 +547,105 @@ impl Parser {
         }
     }
 
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+
+        while self.cond_advance(vec![TT::Dot]) {
+            let name = self
+                .consume(TT::Identifier, "Expected property name after '.'.")?
+                .to_owned();
+            expr = if self.cond_advance(vec![TT::LParen]) {
+                self.control(expr, name)?
+            } else {
+                Get::new(expr, name).into_expr()
+            };
+        }
+
+        Ok(expr)
+    }
+
     fn primary(&mut self) -> Result<Expr, ParseError> {
         match self.peek().ttype {
             TT::False => {
-                self.advance();
-                Ok(Literal::new(Object::Boolean(false)).into_expr())
+                let span = self.advance().span;
+                Ok(Literal::new(Object::Boolean(false), span).into_expr())
             }
             TT::True => {
-                self.advance();
-                Ok(Literal::new(Object::Boolean(true)).into_expr())
+                let span = self.advance().span;
+                Ok(Literal::new(Object::Boolean(true), span).into_expr())
             }
             TT::Null => {
-                self.advance();
-                Ok(Literal::new(Object::Null).into_expr())
+                let span = self.advance().span;
+                Ok(Literal::new(Object::Null, span).into_expr())
             }
 
-            TT::Number | TT::String => {
+            TT::Number | TT::Degree | TT::String | TT::Colour => {
                 let token = self.advance();
-                return Ok(Literal::new(token.literal.clone().unwrap()).into_expr());
+                Ok(Literal::new(token.literal.clone().unwrap(), token.span).into_expr())
+            }
+            TT::Interpolation => {
+                let token = self.advance().to_owned();
+                self.interpolation(token)
+            }
+
+            TT::Draw => {
+                let start = self.advance().span;
+                self.consume(TT::Dot, "Expected '.' after 'draw'.")?;
+                let name = self
+                    .consume(TT::Identifier, "Expected draw command after 'draw.'.")?
+                    .to_owned();
+                self.consume(TT::LParen, "Expected '(' after draw command.")?;
+                self.draw(start, name)
+            }
+
+            TT::Identifier => {
+                let name = self.advance().to_owned();
+                if self.cond_advance(vec![TT::LParen]) {
+                    self.call(name)
+                } else if self.cond_advance(vec![TT::LBracket]) {
+                    let bracket = self.previous().to_owned();
+                    let index = self.expression()?;
+                    self.consume(TT::RBracket, "Expected ']' after index.")?;
+                    let span = self.span_from(name.span);
+                    Ok(Index::new(name, bracket, index, span).into_expr())
+                } else {
+                    Ok(Variable::new(name).into_expr())
+                }
+            }
+
+            TT::AtIdentifier => {
+                let name = self.advance().to_owned();
+                if name.lexeme == "@unit"
+                    && self.check(TT::Dot)
+                    && self.peek_n(1).ttype == TT::Identifier
+                    && self.peek_n(2).ttype == TT::LParen
+                {
+                    self.advance();
+                    let callee = self.advance().to_owned();
+                    self.advance();
+                    self.unit_control(name, callee)
+                } else if builtins::is_builtin(&name.lexeme) {
+                    Ok(Global::new(name).into_expr())
+                } else {
+                    let message = format!("Unknown built-in '{}'.", name.lexeme);
+                    Err(self.report_error(&name, message.as_str()))
+                }
+            }
+
+            TT::Num | TT::Deg => {
+                let name = self.advance().to_owned();
+                self.consume(
+                    TT::LParen,
+                    format!("Expected '(' after '{}'.", name.lexeme).as_str(),
+                )?;
+                self.call(name)
             }
 
             TT::LParen => {
-                self.advance();
+                let start = self.advance().span;
                 let expr = self.expression()?;
                 self.consume(TT::RParen, "Expected ')' after expression.")?;
-                Ok(Grouping::new(expr).into_expr())
+                Ok(Grouping::new(expr, self.span_from(start)).into_expr())
             }
             _ => {
                 let error = self.report_error(self.peek(), "Expected expression.");
@@ -105,17 +654,202 @@ impl Parser {
         }
     }
 
+    /// Splits an interpolated string into text and the expressions between
+    /// braces, each scanned and parsed on its own. `{{` and `}}` stand for
+    /// literal braces.
+    fn interpolation(&mut self, string: Token) -> Result<Expr, ParseError> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = string.lexeme.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '{' | '}' if chars.next_if(|(_, next)| *next == c).is_some() => text.push(c),
+                '{' => {
+                    let mut depth = 0;
+                    let end = string.lexeme[index..]
+                        .char_indices()
+                        .find(|(_, c)| {
+                            match c {
+                                '{' => depth += 1,
+                                '}' => depth -= 1,
+                                _ => (),
+                            }
+                            depth == 0
+                        })
+                        .map(|(end, _)| index + end);
+                    let Some(end) = end else {
+                        return Err(self.report_error(&string, "Expected '}' in string."));
+                    };
+                    while chars.next_if(|(next, _)| *next <= end).is_some() {}
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    let source = &string.lexeme[(index + 1)..end];
+                    if source.trim().is_empty() {
+                        let placeholder = Token {
+                            lexeme: string.lexeme[index..=end].to_owned(),
+                            span: string_span(&string, index, end + 1),
+                            ..string.to_owned()
+                        };
+                        return Err(
+                            self.report_error(&placeholder, "Expected expression inside '{}'.")
+                        );
+                    }
+                    parts.push(Part::Value(Box::new(
+                        self.embedded(&string, index, source)?,
+                    )));
+                }
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Interpolation::new(string, parts).into_expr())
+    }
+
+    /// Parses the expression `source` found after the '{' at byte `index` of
+    /// `string`, moving its tokens' spans to where they are in the file.
+    fn embedded(&mut self, string: &Token, index: usize, source: &str) -> Result<Expr, ParseError> {
+        let mut scanner = Scanner::new(source.into()).with_file(string.span.file);
+        let mut tokens = scanner.scan_tokens();
+        if let Some(error) = scanner.errors().first() {
+            return Err(self.report_error(string, &error.message));
+        }
+
+        let origin = string_span(string, index + 1, index + 1);
+        for token in &mut tokens {
+            let span = &mut token.span;
+            span.start += origin.start;
+            span.end += origin.start;
+            if span.ln == 1 {
+                span.col += origin.col - 1;
+            }
+            span.ln += origin.ln - 1;
+        }
+
+        let mut parser = Parser::new(tokens);
+        let expression = parser.expression()?;
+        if !parser.is_at_end() {
+            return Err(parser.report_error(parser.peek(), "Expected '}' after expression."));
+        }
+        Ok(expression)
+    }
+
+    fn call(&mut self, callee: Token) -> Result<Expr, ParseError> {
+        let (arguments, paren) = self.arguments()?;
+        match builtins::lookup(&callee.lexeme) {
+            Some(signature) => {
+                self.check_arity(signature, &arguments, &paren)?;
+                Ok(Builtin::new(callee, paren, arguments).into_expr())
+            }
+            None => Ok(Call::new(callee, paren, arguments).into_expr()),
+        }
+    }
+
+    fn control(&mut self, object: Expr, name: Token) -> Result<Expr, ParseError> {
+        let (arguments, paren) = self.arguments()?;
+        let Some(signature) = builtins::control(&name.lexeme) else {
+            let message = format!("Unknown building control '{}'.", name.lexeme);
+            return Err(self.report_error(&name, message.as_str()));
+        };
+
+        self.check_arity(signature, &arguments, &paren)?;
+        Ok(Control::new(object, name, paren, arguments).into_expr())
+    }
+
+    fn unit_control(&mut self, unit: Token, callee: Token) -> Result<Expr, ParseError> {
+        let (arguments, paren) = self.arguments()?;
+        let Some(signature) = builtins::unit_control(&callee.lexeme) else {
+            let message = format!("Unknown unit control '{}'.", callee.lexeme);
+            return Err(self.report_error(&callee, message.as_str()));
+        };
+
+        self.check_arity(signature, &arguments, &paren)?;
+        Ok(Builtin::unit_control(unit, callee, paren, arguments).into_expr())
+    }
+
+    fn draw(&mut self, start: Span, callee: Token) -> Result<Expr, ParseError> {
+        let (arguments, paren) = self.arguments()?;
+        let Some(signature) = builtins::draw(&callee.lexeme) else {
+            let message = format!("Unknown draw command '{}'.", callee.lexeme);
+            return Err(self.report_error(&callee, message.as_str()));
+        };
+
+        self.check_arity(signature, &arguments, &paren)?;
+        Ok(Draw::new(callee, paren, arguments, self.span_from(start)).into_expr())
+    }
+
+    /// Parses a call's arguments after its opening parenthesis.
+    fn arguments(&mut self) -> Result<(Vec<Box<dyn ExprLike>>, Token), ParseError> {
+        let mut arguments: Vec<Box<dyn ExprLike>> = vec![];
+        if !self.check(TT::RParen) {
+            loop {
+                arguments.push(Box::new(self.expression()?));
+                if !self.cond_advance(vec![TT::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self
+            .consume(TT::RParen, "Expected ')' after arguments.")?
+            .to_owned();
+        Ok((arguments, paren))
+    }
+
+    fn check_arity(
+        &self,
+        signature: &builtins::Signature,
+        arguments: &[Box<dyn ExprLike>],
+        paren: &Token,
+    ) -> Result<(), ParseError> {
+        if signature.arity == arguments.len()
+            || (signature.variadic && signature.arity < arguments.len())
+        {
+            return Ok(());
+        }
+
+        let message = format!(
+            "Expected {}{} arguments but got {}.",
+            if signature.variadic { "at least " } else { "" },
+            signature.arity,
+            arguments.len()
+        );
+        Err(self.report_error(paren, message.as_str()))
+    }
+
+    /// Skips tokens until the start of the next statement, or the '}' closing
+    /// the block the error was in.
     fn synchronise(&mut self) {
-        self.advance();
+        if !self.check(TT::RBrace) && !self.check(TT::LBrace) {
+            self.advance();
+        }
 
         while !self.is_at_end() {
-            match self.previous().ttype {
-                TT::Semicolon => return,
-                _ => {}
+            // A block belongs to the broken statement, so its closing brace
+            // mustn't be taken for the end of an enclosing block.
+            if self.check(TT::LBrace) {
+                self.skip_block();
+                if !self.check(TT::Else) {
+                    return;
+                }
+            } else if self.previous().ttype == TT::Semicolon {
+                return;
             }
 
             match self.peek().ttype {
-                TT::Class | TT::Fun | TT::Var | TT::For | TT::While | TT::If | TT::Return => return,
+                TT::Class
+                | TT::Fun
+                | TT::Link
+                | TT::Var
+                | TT::For
+                | TT::While
+                | TT::If
+                | TT::Return => return,
+                TT::RBrace => return,
                 _ => {}
             }
 
@@ -123,9 +857,27 @@ impl Parser {
         }
     }
 
+    /// Skips a `{` and everything up to its matching `}`.
+    fn skip_block(&mut self) {
+        let mut depth = 0;
+        while !self.is_at_end() {
+            match self.advance().ttype {
+                TT::LBrace => depth += 1,
+                TT::RBrace => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
     fn consume(&mut self, ttype: TT, message: &str) -> Result<&Token, ParseError> {
         if self.check(ttype) {
             Ok(self.advance())
+        } else if ttype == TT::Semicolon && self.current > 0 {
+            let error = self.report_error(self.peek(), message);
+            Err(error.with_label(self.previous().span, "expected ';' after this".to_owned()))
         } else {
             Err(self.report_error(self.peek(), message))
         }
@@ -161,6 +913,11 @@ impl Parser {
         }
     }
 
+    /// The span from `start` to the end of the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
     fn is_at_end(&self) -> bool {
         self.peek().ttype == TT::EOF
     }
@@ -169,13 +926,50 @@ impl Parser {
         self.tokens.get(self.current).unwrap()
     }
 
+    /// The token `n` past the current one, or the final EOF when there are
+    /// fewer than that left.
     fn peek_n(&self, n: usize) -> &Token {
-        self.tokens.get(self.current + n).unwrap()
+        let index = (self.current + n).min(self.tokens.len() - 1);
+        &self.tokens[index]
     }
 
     fn report_error(&self, token: &Token, message: &str) -> ParseError {
-        let error = ParseError::new(token, message.to_owned());
-        parse_error(&error);
-        error
+        ParseError::new(token, message.to_owned())
+    }
+}
+
+/// The binary operator applied by a compound assignment token.
+/// Where bytes `start..end` of the lexeme of `string` are in the file. The
+/// lexeme leaves out the opening quote.
+fn string_span(string: &Token, start: usize, end: usize) -> Span {
+    let before = &string.lexeme[..start];
+    let col = match before.rfind('\n') {
+        Some(newline) => before[(newline + 1)..].chars().count() + 1,
+        None => string.span.col + 1 + before.chars().count(),
+    };
+    Span {
+        start: string.span.start + 1 + start,
+        end: string.span.start + 1 + end,
+        ln: string.span.ln + before.matches('\n').count(),
+        col,
+        ..string.span
+    }
+}
+
+fn compound_operator(ttype: TT) -> Option<TT> {
+    match ttype {
+        TT::PlusEquals => Some(TT::Plus),
+        TT::MinusEquals => Some(TT::Minus),
+        TT::AstEquals => Some(TT::Ast),
+        TT::Ast2Equals => Some(TT::Ast2),
+        TT::SlashEquals => Some(TT::Slash),
+        TT::Slash2Equals => Some(TT::Slash2),
+        TT::PercentEquals => Some(TT::Percent),
+        TT::LAngle2Equals => Some(TT::LAngle2),
+        TT::RAngle2Equals => Some(TT::RAngle2),
+        TT::AmpEquals => Some(TT::Amp),
+        TT::BarEquals => Some(TT::Bar),
+        TT::HatEquals => Some(TT::Hat),
+        _ => None,
     }
 }
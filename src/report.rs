@@ -0,0 +1,170 @@
+use std::fmt::Write;
+
+use crate::error::{Diagnostic, Severity};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// An underline beneath part of a source line.
+struct Mark<'a> {
+    /// Zero-based character column.
+    col: usize,
+    width: usize,
+    primary: bool,
+    message: Option<&'a str>,
+}
+
+/// Renders `diagnostic` rustc-style: the source lines it points at with its
+/// span and labels underlined, followed by its notes and help. `source` is
+/// the contents of the file called `name`, and ANSI colours are only used
+/// when `colour` is set.
+pub fn render(diagnostic: &Diagnostic, name: &str, source: &str, colour: bool) -> String {
+    let paint = |style: &'static str| if colour { style } else { "" };
+    let reset = paint(RESET);
+    let (severity, accent) = match diagnostic.severity {
+        Severity::Error => ("error", paint(RED)),
+        Severity::Warning => ("warning", paint(YELLOW)),
+    };
+    let blue = paint(BLUE);
+    let bold = paint(BOLD);
+
+    let annotations = &diagnostic.annotations;
+    let mut lines: Vec<(usize, Vec<Mark>)> = vec![];
+    let spans = std::iter::once((diagnostic.span, true, None)).chain(
+        annotations
+            .labels
+            .iter()
+            .map(|label| (label.span, false, Some(label.message.as_str()))),
+    );
+    for (span, primary, message) in spans {
+        // Spans running over several lines are only underlined on the first.
+        let text = source.get(span.start..span.end).unwrap_or("");
+        let width = text.split('\n').next().unwrap_or("").chars().count();
+        let mark = Mark {
+            col: span.col - 1,
+            width: width.max(1),
+            primary,
+            message,
+        };
+        match lines.iter_mut().find(|(ln, _)| *ln == span.ln) {
+            Some((_, marks)) => marks.push(mark),
+            None => lines.push((span.ln, vec![mark])),
+        }
+    }
+    lines.sort_by_key(|(ln, _)| *ln);
+
+    let gutter = lines.last().map_or(1, |(ln, _)| ln.to_string().len());
+    let pad = " ".repeat(gutter);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{accent}{severity}{reset}{bold}: {}{reset}",
+        diagnostic.message
+    );
+    let _ = writeln!(
+        out,
+        "{pad}{blue}-->{reset} {name}:{}:{}",
+        diagnostic.span.ln, diagnostic.span.col
+    );
+    let _ = writeln!(out, "{pad} {blue}|{reset}");
+
+    let mut previous = None;
+    for (ln, mut marks) in lines {
+        if previous.is_some_and(|previous| ln > previous + 1) {
+            let _ = writeln!(out, "{blue}...{reset}");
+        }
+        previous = Some(ln);
+
+        let text = source.split('\n').nth(ln - 1).unwrap_or("");
+        let text = text.trim_end_matches('\r').replace('\t', " ");
+        let _ = writeln!(out, "{blue}{ln:>gutter$} |{reset} {text}");
+
+        marks.sort_by_key(|mark| (mark.col, mark.primary));
+        let style = |mark: &Mark| if mark.primary { accent } else { blue };
+
+        // The rightmost message goes beside the underlines, and the others
+        // hang below them on lines joined by '|'.
+        let mut underline = String::new();
+        let mut used = 0;
+        for mark in &marks {
+            let start = mark.col.max(used);
+            let width = (mark.col + mark.width).saturating_sub(start);
+            if width == 0 {
+                continue;
+            }
+            let symbol = if mark.primary { "^" } else { "-" };
+            underline += &" ".repeat(start - used);
+            underline += &format!("{}{}{reset}", style(mark), symbol.repeat(width));
+            used = start + width;
+        }
+        let mut hanging: Vec<&Mark> = marks.iter().filter(|mark| mark.message.is_some()).collect();
+        if let Some(last) = hanging.last() {
+            if marks.iter().all(|mark| mark.col <= last.col) {
+                let message = last.message.unwrap_or("");
+                underline += &format!(" {}{message}{reset}", style(last));
+                hanging.pop();
+            }
+        }
+        let _ = writeln!(out, "{pad} {blue}|{reset} {underline}");
+
+        let connectors = |marks: &[&Mark]| {
+            let mut line = String::new();
+            for mark in marks {
+                // Marks in the same column share one connector.
+                let width = line_width(&line, colour);
+                if mark.col < width {
+                    continue;
+                }
+                line += &" ".repeat(mark.col - width);
+                line += &format!("{}|{reset}", style(mark));
+            }
+            line
+        };
+        if !hanging.is_empty() {
+            let _ = writeln!(out, "{pad} {blue}|{reset} {}", connectors(&hanging));
+        }
+        for index in (0..hanging.len()).rev() {
+            let mark = hanging[index];
+            let left: Vec<&Mark> = hanging[..index]
+                .iter()
+                .filter(|earlier| earlier.col < mark.col)
+                .copied()
+                .collect();
+            let mut line = connectors(&left);
+            line += &" ".repeat(mark.col.saturating_sub(line_width(&line, colour)));
+            line += &format!("{}{}{reset}", style(mark), mark.message.unwrap_or(""));
+            let _ = writeln!(out, "{pad} {blue}|{reset} {line}");
+        }
+    }
+
+    for note in &annotations.notes {
+        let _ = writeln!(out, "{pad} {blue}={reset} {bold}note{reset}: {note}");
+    }
+    for help in &annotations.help {
+        let _ = writeln!(out, "{pad} {blue}={reset} {bold}help{reset}: {help}");
+    }
+    out
+}
+
+/// The number of characters `line` takes up on screen, skipping colour codes.
+fn line_width(line: &str, colour: bool) -> usize {
+    if !colour {
+        return line.chars().count();
+    }
+
+    let mut width = 0;
+    let mut escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
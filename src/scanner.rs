@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::default;
 
-use crate::error;
+use crate::builtins;
+use crate::error::{Diagnostic, Stage};
 use crate::token::Object;
-use crate::token::Position;
+use crate::token::Span;
 use crate::token::Token;
 use crate::token::TokenType as TT;
 
@@ -15,6 +16,11 @@ pub struct Scanner {
     startln: usize,
     current: usize,
     ln: usize,
+    startcol: usize,
+    /// Byte offset of the first character on line `ln`.
+    line_start: usize,
+    file: usize,
+    errors: Vec<Diagnostic>,
 }
 
 impl default::Default for Scanner {
@@ -26,6 +32,10 @@ impl default::Default for Scanner {
             startln: 1,
             current: 0,
             ln: 1,
+            startcol: 1,
+            line_start: 0,
+            file: 0,
+            errors: vec![],
         }
     }
 }
@@ -45,10 +55,23 @@ impl Scanner {
         }
     }
 
+    /// Tags every span with `file`, to tell apart tokens from several
+    /// sources.
+    pub fn with_file(mut self, file: usize) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Errors found by `scan_tokens`, which skips over them and carries on.
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+
     pub fn scan_tokens(&mut self) -> Vec<Token> {
         while !(self.is_at_end()) {
             self.start = self.current;
             self.startln = self.ln;
+            self.startcol = self.column(self.start);
 
             // scan_token
             {
@@ -59,16 +82,23 @@ impl Scanner {
                     (')', _) => self.add_token(TT::RParen),
                     ('{', _) => self.add_token(TT::LBrace),
                     ('}', _) => self.add_token(TT::RBrace),
+                    ('[', _) => self.add_token(TT::LBracket),
+                    (']', _) => self.add_token(TT::RBracket),
                     (',', _) => self.add_token(TT::Comma),
                     ('.', _) => self.add_token(TT::Dot),
-                    ('+', _) => self.add_token(TT::Plus),
-                    ('-', _) => self.add_token(TT::Minus),
+                    (':', _) => self.add_token(TT::Colon),
+                    ('?', _) => self.add_token(TT::Question),
+                    ('+', _) => self.add_operator(TT::Plus, TT::PlusEquals),
+                    ('-', _) => self.add_operator(TT::Minus, TT::MinusEquals),
                     (';', _) => self.add_token(TT::Semicolon),
-                    ('^', _) => self.add_token(TT::Hat),
+                    ('^', _) => self.add_operator(TT::Hat, TT::HatEquals),
                     ('~', _) => self.add_token(TT::Tilde),
-                    ('|', _) => self.add_token(TT::Bar),
-                    ('&', _) => self.add_token(TT::Amp),
-                    ('%', _) => self.add_token(TT::Percent),
+                    ('|', _) => self.add_operator(TT::Bar, TT::BarEquals),
+                    ('&', _) => self.add_operator(TT::Amp, TT::AmpEquals),
+                    ('%', _) if self.after_operand() => {
+                        self.add_operator(TT::Percent, TT::PercentEquals)
+                    }
+                    ('%', _) => self.colour(),
                     ('!', '=') => {
                         self.advance();
                         self.add_token(TT::BangEquals)
@@ -94,14 +124,13 @@ impl Scanner {
                         if !res {
                             res2 = self.cond_advance('<');
                         }
-                        let ty = if !(res || res2) {
-                            TT::LAngle
+                        if !(res || res2) {
+                            self.add_token(TT::LAngle)
                         } else if res {
-                            TT::LAngleEquals
+                            self.add_token(TT::LAngleEquals)
                         } else {
-                            TT::LAngle2
-                        };
-                        self.add_token(ty)
+                            self.add_operator(TT::LAngle2, TT::LAngle2Equals)
+                        }
                     }
                     ('>', _) => {
                         let res = self.cond_advance('=');
@@ -109,25 +138,28 @@ impl Scanner {
                         if !res {
                             res2 = self.cond_advance('>');
                         }
-                        let ty = if !(res || res2) {
-                            TT::RAngle
+                        if !(res || res2) {
+                            self.add_token(TT::RAngle)
                         } else if res {
-                            TT::RAngleEquals
+                            self.add_token(TT::RAngleEquals)
                         } else {
-                            TT::RAngle2
-                        };
-                        self.add_token(ty)
+                            self.add_operator(TT::RAngle2, TT::RAngle2Equals)
+                        }
                     }
                     ('*', _) => {
-                        let res = self.cond_advance('*');
-                        let ty = if res { TT::Ast2 } else { TT::Ast };
-                        self.add_token(ty)
+                        if self.cond_advance('*') {
+                            self.add_operator(TT::Ast2, TT::Ast2Equals)
+                        } else {
+                            self.add_operator(TT::Ast, TT::AstEquals)
+                        }
                     }
 
                     ('/', _) => {
-                        let res = self.cond_advance('/');
-                        let ty = if res { TT::Slash2 } else { TT::Slash };
-                        self.add_token(ty)
+                        if self.cond_advance('/') {
+                            self.add_operator(TT::Slash2, TT::Slash2Equals)
+                        } else {
+                            self.add_operator(TT::Slash, TT::SlashEquals)
+                        }
                     }
                     ('#', _) => {
                         while self.peek() != '\n' && !self.is_at_end() {
@@ -138,27 +170,66 @@ impl Scanner {
                     (' ', _) => {}
                     ('\r', _) => {}
                     ('\t', _) => {}
-                    ('\n', _) => self.ln += 1,
+                    ('\n', _) => self.newline(),
 
                     ('"', _) => {
                         self.string();
                     }
 
+                    ('@', _) => {
+                        while self.peek().is_ascii_alphanumeric()
+                            || self.peek() == '_'
+                            || self.peek() == '-'
+                        {
+                            self.advance();
+                        }
+                        // `-` joins the words of content names like
+                        // `@phase-fabric`, but in `@time-1` it's a minus.
+                        let name = String::from_utf8(self.source[self.start..self.current].into())
+                            .unwrap();
+                        let mut length = name.len();
+                        while !builtins::is_builtin(&name[..length]) {
+                            match name[..length].rfind('-') {
+                                Some(end) => length = end,
+                                None => {
+                                    length = name.len();
+                                    break;
+                                }
+                            }
+                        }
+                        self.current = self.start + length;
+                        self.add_token(TT::AtIdentifier)
+                    }
+
                     _ => {
                         if c.is_ascii_digit() {
                             self.number();
                         } else if c.is_ascii_alphabetic() || c == '_' {
                             self.identifier();
                         } else {
-                            error(self.ln, self.start + 1, "Unexpected character.".to_owned())
+                            // Take the whole of a multi-byte character.
+                            while self
+                                .source
+                                .get(self.current)
+                                .is_some_and(|b| b & 0xC0 == 0x80)
+                            {
+                                self.current += 1;
+                            }
+                            self.error("Unexpected character.".to_owned())
                         }
                     }
                 }
             }
         }
 
-        self.tokens
-            .push(Token::eof(Position::new(self.ln, self.current + 1)));
+        let end = Span::new(
+            self.file,
+            self.current,
+            self.current,
+            self.ln,
+            self.column(self.current),
+        );
+        self.tokens.push(Token::eof(end));
 
         self.tokens.clone()
     }
@@ -170,7 +241,12 @@ impl Scanner {
         keywords.insert("not", TT::Not);
         keywords.insert("while", TT::While);
         keywords.insert("for", TT::For);
+        keywords.insert("loop", TT::Loop);
+        keywords.insert("break", TT::Break);
+        keywords.insert("continue", TT::Continue);
         keywords.insert("if", TT::If);
+        keywords.insert("in", TT::In);
+        keywords.insert("then", TT::Then);
         keywords.insert("else", TT::Else);
         keywords.insert("null", TT::Null);
         keywords.insert("true", TT::True);
@@ -181,29 +257,11 @@ impl Scanner {
         keywords.insert("this", TT::This);
         keywords.insert("super", TT::Super);
         keywords.insert("var", TT::Var);
+        keywords.insert("link", TT::Link);
 
         keywords.insert("num", TT::Num);
         keywords.insert("deg", TT::Deg);
-
-        // keywords.insert("max", TT::Max);
-        // keywords.insert("min", TT::Min);
-        // keywords.insert("angle", TT::And);
-        // keywords.insert("angleDiff", TT::And);
-        // keywords.insert("len", TT::And);
-        // keywords.insert("noise", TT::And);
-        // keywords.insert("abs", TT::And);
-        // keywords.insert("log", TT::And);
-        // keywords.insert("log10", TT::And);
-        // keywords.insert("floor", TT::And);
-        // keywords.insert("ceil", TT::And);
-        // keywords.insert("sqrt", TT::And);
-        // keywords.insert("rand", TT::And);
-        // keywords.insert("sin", TT::And);
-        // keywords.insert("cos", TT::And);
-        // keywords.insert("tan", TT::And);
-        // keywords.insert("asin", TT::And);
-        // keywords.insert("acos", TT::And);
-        // keywords.insert("atan", TT::And);
+        keywords.insert("draw", TT::Draw);
 
         while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
             self.advance();
@@ -219,6 +277,72 @@ impl Scanner {
         self.add_token(ty)
     }
 
+    /// Whether the last token ends an operand, after which `%` can only be
+    /// the modulo operator and never starts a colour.
+    fn after_operand(&self) -> bool {
+        self.tokens.last().is_some_and(|token| {
+            matches!(
+                token.ttype,
+                TT::Identifier
+                    | TT::String
+                    | TT::Interpolation
+                    | TT::Number
+                    | TT::Degree
+                    | TT::Colour
+                    | TT::AtIdentifier
+                    | TT::True
+                    | TT::False
+                    | TT::Null
+                    | TT::RParen
+                    | TT::RBracket
+            )
+        })
+    }
+
+    fn colour(&mut self) {
+        if self.peek() == '[' {
+            self.advance();
+            while self.peek() != ']' && !self.is_at_end() {
+                self.advance();
+            }
+
+            if self.is_at_end() {
+                self.error("Unterminated colour name.".to_owned());
+                return;
+            }
+            self.advance();
+
+            let name = String::from_utf8(self.source[(self.start + 2)..(self.current - 1)].into())
+                .unwrap();
+            match builtins::named_colour(&name) {
+                Some(packed) => self.add_literal(TT::Colour, Object::from_rgba8888(packed)),
+                None => self.error(format!("Unknown colour name '{name}'.")),
+            }
+            return;
+        }
+
+        let mut digits = 0;
+        while self.peek_n(digits).is_ascii_hexdigit() {
+            digits += 1;
+        }
+
+        let next = self.peek_n(digits);
+        if !(digits == 6 || digits == 8) || next.is_ascii_alphanumeric() || next == '_' {
+            self.add_operator(TT::Percent, TT::PercentEquals);
+            return;
+        }
+
+        self.current += digits;
+        let hex = String::from_utf8(self.source[(self.start + 1)..self.current].into()).unwrap();
+        let packed = u32::from_str_radix(&hex, 16).unwrap();
+        let packed = if digits == 6 {
+            packed << 8 | 0xff
+        } else {
+            packed
+        };
+        self.add_literal(TT::Colour, Object::from_rgba8888(packed));
+    }
+
     fn number(&mut self) {
         while self.peek().is_ascii_digit() {
             self.advance();
@@ -235,10 +359,6 @@ impl Scanner {
         let ttype: TT;
         let lexeme: String;
         let literal: Option<Object>;
-        let position: Position;
-
-        position = Position::new(self.startln, self.start + 1);
-
         if self.peek() == 'd'
             && self.peek_next() == 'e'
             && self.peek_n(2) == 'g'
@@ -269,45 +389,104 @@ impl Scanner {
             ttype,
             lexeme,
             literal,
-            position,
+            span: self.span(),
         });
     }
 
     fn string(&mut self) {
         while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.ln += 1
-            };
-            self.advance();
+            if self.advance() == '\n' {
+                self.newline();
+            }
         }
 
         if self.is_at_end() {
-            error(self.ln, self.current, "Unterminated string.".to_owned());
+            self.error("Unterminated string.".to_owned());
         } else {
             self.advance();
             let lexeme =
                 String::from_utf8(self.source[(self.start + 1)..(self.current - 1)].into())
                     .unwrap();
-            let position = Position::new(self.startln, self.start + 1);
-            let literal = Some(Object::String(lexeme.to_owned()));
+            // `{{` and `}}` write a brace, and a `}` on its own needs no
+            // escaping.
+            let (ttype, literal) = if interpolates(&lexeme) {
+                (TT::Interpolation, lexeme.to_owned())
+            } else {
+                (TT::String, lexeme.replace("{{", "{").replace("}}", "}"))
+            };
+            let literal = Some(Object::String(literal));
 
             self.tokens.push(Token {
-                ttype: TT::String,
+                ttype,
                 lexeme,
                 literal,
-                position,
+                span: self.span(),
             })
         }
     }
 
+    /// Reports an error covering the characters scanned for this token,
+    /// leaving an error token in their place.
+    fn error(&mut self, message: String) {
+        self.errors
+            .push(Diagnostic::at(Stage::Scan, self.span(), message));
+        // The characters may not even be valid UTF-8.
+        let lexeme = String::from_utf8_lossy(&self.source[self.start..self.current]).into_owned();
+        self.tokens.push(Token {
+            ttype: TT::Error,
+            lexeme,
+            literal: None,
+            span: self.span(),
+        });
+    }
+
+    /// The span of the token being scanned.
+    fn span(&self) -> Span {
+        Span::new(
+            self.file,
+            self.start,
+            self.current,
+            self.startln,
+            self.startcol,
+        )
+    }
+
+    /// The column of byte `offset` on the current line, counting characters
+    /// rather than bytes.
+    fn column(&self, offset: usize) -> usize {
+        let line = &self.source[self.line_start..offset];
+        line.iter().filter(|b| *b & 0xC0 != 0x80).count() + 1
+    }
+
+    /// Moves to the next line, having just consumed a newline.
+    fn newline(&mut self) {
+        self.ln += 1;
+        self.line_start = self.current;
+    }
+
+    fn add_literal(&mut self, ty: TT, literal: Object) {
+        let text = String::from_utf8(self.source[self.start..self.current].into()).unwrap();
+        self.tokens.push(Token {
+            ttype: ty,
+            lexeme: text,
+            literal: Some(literal),
+            span: self.span(),
+        })
+    }
+
+    /// Adds `compound` instead of `ty` when the operator is followed by `=`.
+    fn add_operator(&mut self, ty: TT, compound: TT) {
+        let ty = if self.cond_advance('=') { compound } else { ty };
+        self.add_token(ty)
+    }
+
     fn add_token(&mut self, ty: TT) {
         let text = String::from_utf8(self.source[self.start..self.current].into()).unwrap();
-        let position = Position::new(self.startln, self.start + 1);
         self.tokens.push(Token {
             ttype: ty,
             lexeme: text,
             literal: None,
-            position,
+            span: self.span(),
         })
     }
 
@@ -331,7 +510,7 @@ impl Scanner {
             return false;
         }
 
-        return true;
+        true
     }
 
     fn peek(&self) -> char {
@@ -346,3 +525,15 @@ impl Scanner {
         *self.source.get(self.current + n).unwrap_or(&0u8) as char
     }
 }
+
+/// Whether `string` has a `{` starting an embedded expression, rather than
+/// only `{{` escapes.
+fn interpolates(string: &str) -> bool {
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        if c == '{' && chars.next() != Some('{') {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,571 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{
+    codegen::Emittable,
+    expr::ExprLike,
+    interpreter::Executable,
+    optimiser::Optimisable,
+    token::{Span, Token},
+};
+
+pub trait StmtLike: fmt::Display + Executable + Emittable + Optimisable {
+    /// The source range the statement was parsed from.
+    fn span(&self) -> Span;
+}
+pub trait IntoStmt {
+    fn into_stmt(self) -> Stmt;
+}
+
+#[derive(Clone)]
+pub struct Stmt(pub Rc<RefCell<dyn StmtLike>>);
+
+impl StmtLike for Stmt {
+    fn span(&self) -> Span {
+        self.0.borrow().span()
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.borrow())
+    }
+}
+
+// ========== Expression ==========
+
+pub struct Expression {
+    pub expression: Box<dyn ExprLike>,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(expression: impl ExprLike + 'static, span: Span) -> Self {
+        Self {
+            expression: Box::new(expression),
+            span,
+        }
+    }
+}
+
+impl StmtLike for Expression {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(expr {})", self.expression)
+    }
+}
+
+impl IntoStmt for Expression {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Var ==========
+
+pub struct Var {
+    pub name: Token,
+    pub initializer: Option<Box<dyn ExprLike>>,
+    pub span: Span,
+}
+
+impl Var {
+    pub fn new(name: Token, initializer: Option<impl ExprLike + 'static>, span: Span) -> Self {
+        Self {
+            name,
+            initializer: initializer.map(|expr| Box::new(expr) as Box<dyn ExprLike>),
+            span,
+        }
+    }
+}
+
+impl StmtLike for Var {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.initializer {
+            Some(initializer) => write!(f, "(var {} {})", self.name.lexeme, initializer),
+            None => write!(f, "(var {})", self.name.lexeme),
+        }
+    }
+}
+
+impl IntoStmt for Var {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Destructure ==========
+
+/// `var (a, b, ...) = value;` for calls returning several values.
+pub struct Destructure {
+    pub paren: Token,
+    pub names: Vec<Token>,
+    pub initializer: Box<dyn ExprLike>,
+    pub span: Span,
+}
+
+impl Destructure {
+    pub fn new(
+        paren: Token,
+        names: Vec<Token>,
+        initializer: impl ExprLike + 'static,
+        span: Span,
+    ) -> Self {
+        Self {
+            paren,
+            names,
+            initializer: Box::new(initializer),
+            span,
+        }
+    }
+}
+
+impl StmtLike for Destructure {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Destructure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.names.iter().map(|name| name.lexeme.as_str()).collect();
+        write!(f, "(var ({}) {})", names.join(" "), self.initializer)
+    }
+}
+
+impl IntoStmt for Destructure {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Array ==========
+
+/// `var name: [size] in block;`, an array stored in a memory cell or bank.
+pub struct Array {
+    pub name: Token,
+    pub size: usize,
+    pub block: Token,
+    pub span: Span,
+}
+
+impl Array {
+    pub fn new(name: Token, size: usize, block: Token, span: Span) -> Self {
+        Self {
+            name,
+            size,
+            block,
+            span,
+        }
+    }
+}
+
+impl StmtLike for Array {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Array {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "(var {} [{}] {})",
+            self.name.lexeme, self.size, self.block.lexeme
+        )
+    }
+}
+
+impl IntoStmt for Array {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Block ==========
+
+pub struct Block {
+    pub statements: Vec<Stmt>,
+    pub span: Span,
+}
+
+impl Block {
+    pub fn new(statements: Vec<Stmt>, span: Span) -> Self {
+        Self { statements, span }
+    }
+}
+
+impl StmtLike for Block {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(block")?;
+        for statement in &self.statements {
+            write!(f, " {statement}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoStmt for Block {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== If ==========
+
+pub struct If {
+    pub keyword: Token,
+    pub condition: Box<dyn ExprLike>,
+    pub then_branch: Box<dyn StmtLike>,
+    pub else_branch: Option<Box<dyn StmtLike>>,
+    pub span: Span,
+}
+
+impl If {
+    pub fn new(
+        keyword: Token,
+        condition: impl ExprLike + 'static,
+        then_branch: impl StmtLike + 'static,
+        else_branch: Option<impl StmtLike + 'static>,
+        span: Span,
+    ) -> Self {
+        Self {
+            keyword,
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: else_branch.map(|stmt| Box::new(stmt) as Box<dyn StmtLike>),
+            span,
+        }
+    }
+}
+
+impl StmtLike for If {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for If {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.else_branch {
+            Some(else_branch) => write!(
+                f,
+                "(if {} {} {})",
+                self.condition, self.then_branch, else_branch
+            ),
+            None => write!(f, "(if {} {})", self.condition, self.then_branch),
+        }
+    }
+}
+
+impl IntoStmt for If {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== While ==========
+
+pub struct While {
+    pub label: Option<Token>,
+    pub keyword: Token,
+    pub condition: Option<Box<dyn ExprLike>>,
+    pub body: Box<dyn StmtLike>,
+    pub span: Span,
+}
+
+impl While {
+    pub fn new(
+        label: Option<Token>,
+        keyword: Token,
+        condition: Option<impl ExprLike + 'static>,
+        body: impl StmtLike + 'static,
+        span: Span,
+    ) -> Self {
+        Self {
+            label,
+            keyword,
+            condition: condition.map(|expr| Box::new(expr) as Box<dyn ExprLike>),
+            body: Box::new(body),
+            span,
+        }
+    }
+}
+
+impl StmtLike for While {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for While {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        if let Some(label) = &self.label {
+            write!(f, "{}: ", label.lexeme)?;
+        }
+        match &self.condition {
+            Some(condition) => write!(f, "while {} {})", condition, self.body),
+            None => write!(f, "loop {})", self.body),
+        }
+    }
+}
+
+impl IntoStmt for While {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== For ==========
+
+pub struct For {
+    pub label: Option<Token>,
+    pub keyword: Token,
+    pub initializer: Option<Box<dyn StmtLike>>,
+    pub condition: Option<Box<dyn ExprLike>>,
+    pub increment: Option<Box<dyn ExprLike>>,
+    pub body: Box<dyn StmtLike>,
+    pub span: Span,
+}
+
+impl For {
+    pub fn new(
+        label: Option<Token>,
+        keyword: Token,
+        initializer: Option<impl StmtLike + 'static>,
+        condition: Option<impl ExprLike + 'static>,
+        increment: Option<impl ExprLike + 'static>,
+        body: impl StmtLike + 'static,
+        span: Span,
+    ) -> Self {
+        Self {
+            label,
+            keyword,
+            initializer: initializer.map(|stmt| Box::new(stmt) as Box<dyn StmtLike>),
+            condition: condition.map(|expr| Box::new(expr) as Box<dyn ExprLike>),
+            increment: increment.map(|expr| Box::new(expr) as Box<dyn ExprLike>),
+            body: Box::new(body),
+            span,
+        }
+    }
+}
+
+impl StmtLike for For {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for For {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        if let Some(label) = &self.label {
+            write!(f, "{}: ", label.lexeme)?;
+        }
+        write!(f, "for")?;
+        match &self.initializer {
+            Some(initializer) => write!(f, " {initializer}")?,
+            None => write!(f, " ()")?,
+        }
+        match &self.condition {
+            Some(condition) => write!(f, " {condition}")?,
+            None => write!(f, " ()")?,
+        }
+        match &self.increment {
+            Some(increment) => write!(f, " {increment}")?,
+            None => write!(f, " ()")?,
+        }
+        write!(f, " {})", self.body)
+    }
+}
+
+impl IntoStmt for For {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Jump ==========
+
+/// `break` or `continue`, told apart by `keyword`.
+pub struct Jump {
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub span: Span,
+}
+
+impl Jump {
+    pub fn new(keyword: Token, label: Option<Token>, span: Span) -> Self {
+        Self {
+            keyword,
+            label,
+            span,
+        }
+    }
+}
+
+impl StmtLike for Jump {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Jump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "({} {})", self.keyword.lexeme, label.lexeme),
+            None => write!(f, "({})", self.keyword.lexeme),
+        }
+    }
+}
+
+impl IntoStmt for Jump {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Link ==========
+
+/// `link cell1, message1;`, declaring the buildings linked to the processor.
+pub struct Link {
+    pub keyword: Token,
+    pub names: Vec<Token>,
+    pub span: Span,
+}
+
+impl Link {
+    pub fn new(keyword: Token, names: Vec<Token>, span: Span) -> Self {
+        Self {
+            keyword,
+            names,
+            span,
+        }
+    }
+}
+
+impl StmtLike for Link {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(link")?;
+        for name in &self.names {
+            write!(f, " {}", name.lexeme)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoStmt for Link {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Function ==========
+
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+impl Function {
+    pub fn new(name: Token, params: Vec<Token>, body: Vec<Stmt>, span: Span) -> Self {
+        Self {
+            name,
+            params,
+            body,
+            span,
+        }
+    }
+}
+
+impl StmtLike for Function {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(fun {} (", self.name.lexeme)?;
+        let params: Vec<&str> = self
+            .params
+            .iter()
+            .map(|param| param.lexeme.as_str())
+            .collect();
+        write!(f, "{})", params.join(" "))?;
+        for statement in &self.body {
+            write!(f, " {statement}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl IntoStmt for Function {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
+
+// ========== Return ==========
+
+pub struct Return {
+    pub keyword: Token,
+    pub value: Option<Box<dyn ExprLike>>,
+    pub span: Span,
+}
+
+impl Return {
+    pub fn new(keyword: Token, value: Option<impl ExprLike + 'static>, span: Span) -> Self {
+        Self {
+            keyword,
+            value: value.map(|expr| Box::new(expr) as Box<dyn ExprLike>),
+            span,
+        }
+    }
+}
+
+impl StmtLike for Return {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Return {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "(return {value})"),
+            None => write!(f, "(return)"),
+        }
+    }
+}
+
+impl IntoStmt for Return {
+    fn into_stmt(self) -> Stmt {
+        Stmt(Rc::new(RefCell::new(self)))
+    }
+}
@@ -5,11 +5,16 @@ pub struct Token {
     pub ttype: TokenType,
     pub lexeme: String,
     pub literal: Option<Object>,
-    pub position: Position,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Position {
+/// A range of bytes in a source file, with the line and column it starts at.
+/// Columns count characters from the last newline, starting at 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: usize,
+    pub start: usize,
+    pub end: usize,
     pub ln: usize,
     pub col: usize,
 }
@@ -20,16 +25,22 @@ pub enum Object {
     Degree(f32),
     String(String),
     Boolean(bool),
+    /// Red, green, blue and alpha components in `[0, 1]`.
+    Colour(f32, f32, f32, f32),
+    /// An `@`-prefixed content, team or sensor constant such as `@copper`.
+    Content(String),
     Null,
 }
 
 impl Object {
-    pub fn dtype(&self) -> &str {
+    pub fn dtype(&self) -> &'static str {
         match self {
             Object::Number(_) => "Number",
             Object::Degree(_) => "Degree",
             Object::String(_) => "String",
             Object::Boolean(_) => "Boolean",
+            Object::Colour(..) => "Colour",
+            Object::Content(_) => "Content",
             Object::Null => "Null",
         }
     }
@@ -38,11 +49,28 @@ impl Object {
         match self {
             Object::Number(x) => Object::Number(*x),
             Object::Degree(x) => Object::Number(*x),
-            Object::String(x) => Object::Number(1.),
+            Object::String(_) => Object::Number(1.),
             Object::Boolean(x) => Object::Number(if *x { 1. } else { 0. }),
+            Object::Colour(..) => Object::Number(self.rgba8888() as f32),
+            Object::Content(_) => Object::Number(1.),
             Object::Null => Object::Number(0.),
         }
     }
+
+    /// Packs a colour into `0xRRGGBBAA`, or 0 for anything else.
+    pub fn rgba8888(&self) -> u32 {
+        match self {
+            Object::Colour(r, g, b, a) => [r, g, b, a].iter().fold(0, |packed, c| {
+                packed << 8 | (c.clamp(0., 1.) * 255.).round() as u32
+            }),
+            _ => 0,
+        }
+    }
+
+    pub fn from_rgba8888(packed: u32) -> Self {
+        let [r, g, b, a] = packed.to_be_bytes().map(|c| c as f32 / 255.);
+        Object::Colour(r, g, b, a)
+    }
 }
 
 impl Display for Object {
@@ -52,6 +80,8 @@ impl Display for Object {
             Object::Degree(value) => write!(f, "{value}deg"),
             Object::String(value) => write!(f, "{value}"),
             Object::Boolean(value) => write!(f, "{value}"),
+            Object::Colour(..) => write!(f, "%{:08x}", self.rgba8888()),
+            Object::Content(name) => write!(f, "{name}"),
             Object::Null => write!(f, "null"),
         }
     }
@@ -60,13 +90,17 @@ impl Display for Object {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     // Single character tokens
-    LParen, // (
-    RParen, // )
-    LBrace, // {
-    RBrace, // }
-    Comma,  // ,
-    Dot,    // .
-    Equals, // =
+    LParen,   // (
+    RParen,   // )
+    LBrace,   // {
+    RBrace,   // }
+    LBracket, // [
+    RBracket, // ]
+    Comma,    // ,
+    Dot,      // .
+    Colon,    // :
+    Question, // ?
+    Equals,   // =
 
     Minus,     // -
     Plus,      // +
@@ -83,6 +117,20 @@ pub enum TokenType {
     Hat,       // ^
     Tilde,     // ~
 
+    // Compound assignment
+    PlusEquals,    // +=
+    MinusEquals,   // -=
+    AstEquals,     // *=
+    Ast2Equals,    // **=
+    SlashEquals,   // /=
+    Slash2Equals,  // //=
+    PercentEquals, // %=
+    LAngle2Equals, // <<=
+    RAngle2Equals, // >>=
+    AmpEquals,     // &=
+    BarEquals,     // |=
+    HatEquals,     // ^=
+
     // Bang,         // !
     BangEquals,   // !=
     Equals2,      // ==
@@ -95,14 +143,21 @@ pub enum TokenType {
     // Literals
     Identifier,
     String,
+    Interpolation,
     Number,
     Degree,
     Colour,
+    AtIdentifier,
 
     // Keywords
     While,
     For,
+    Loop,
+    Break,
+    Continue,
     If,
+    In,
+    Then,
     Else,
     Null,
     And,
@@ -116,37 +171,57 @@ pub enum TokenType {
     Super,
     Var,
     Not,
+    Link,
 
     // Built-in functions
     Num,
     Deg,
+    Draw,
+
+    /// Stands in for characters that failed to scan, which the scanner has
+    /// already reported.
+    Error,
 
     // Last thing
     EOF,
 }
 
 impl Token {
-    pub fn new(
-        ttype: TokenType,
-        lexeme: String,
-        literal: Option<Object>,
-        position: Position,
-    ) -> Token {
+    pub fn new(ttype: TokenType, lexeme: String, literal: Option<Object>, span: Span) -> Token {
         Token {
             ttype,
             lexeme,
             literal,
-            position,
+            span,
         }
     }
 
-    pub fn eof(position: Position) -> Self {
-        Token::new(TokenType::EOF, "".to_owned(), None, position)
+    pub fn eof(span: Span) -> Self {
+        Token::new(TokenType::EOF, "".to_owned(), None, span)
     }
 }
 
-impl Position {
-    pub fn new(ln: usize, col: usize) -> Position {
-        Position { ln, col }
+impl Span {
+    pub fn new(file: usize, start: usize, end: usize, ln: usize, col: usize) -> Span {
+        Span {
+            file,
+            start,
+            end,
+            ln,
+            col,
+        }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        let (first, last) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Span {
+            end: first.end.max(last.end),
+            ..first
+        }
     }
 }
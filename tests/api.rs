@@ -0,0 +1,96 @@
+use mindustry_transpiler::{
+    compile,
+    error::{Diagnostic, Severity, Stage},
+    parse, run, tokens,
+};
+
+/// The stage and message of each diagnostic.
+fn summary(diagnostics: &[Diagnostic]) -> Vec<(Stage, &str)> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.stage, diagnostic.message.as_str()))
+        .collect()
+}
+
+#[test]
+fn each_pass_returns_its_diagnostics() {
+    let Err(diagnostics) = tokens(b"a $".to_vec()) else {
+        panic!("scanning succeeded");
+    };
+    assert_eq!(
+        summary(&diagnostics),
+        [(Stage::Scan, "Unexpected character.")]
+    );
+
+    let Err(diagnostics) = parse(b"var = 1;".to_vec()) else {
+        panic!("parsing succeeded");
+    };
+    assert_eq!(
+        summary(&diagnostics),
+        [(Stage::Parse, "Expected variable name.")]
+    );
+
+    let Err(diagnostics) = run(b"var a = b;".to_vec()) else {
+        panic!("running succeeded");
+    };
+    assert_eq!(
+        summary(&diagnostics),
+        [(Stage::Evaluation, "Undefined variable 'b'.")]
+    );
+
+    let Err(diagnostics) = compile(b"var a = b;".to_vec()) else {
+        panic!("compiling succeeded");
+    };
+    assert_eq!(
+        summary(&diagnostics),
+        [(Stage::Compile, "Undefined variable 'b'.")]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+}
+
+#[test]
+fn diagnostics_point_at_the_source() {
+    let Err(diagnostics) = compile(b"var a = 1;\nvar b = c;".to_vec()) else {
+        panic!("compiling succeeded");
+    };
+    let diagnostic = &diagnostics[0];
+    assert_eq!((diagnostic.span.ln, diagnostic.span.col), (2, 9));
+    assert_eq!((diagnostic.span.start, diagnostic.span.end), (19, 20));
+    assert_eq!(diagnostic.token.as_ref().unwrap().lexeme, "c");
+}
+
+#[test]
+fn annotations_are_returned_with_the_error() {
+    let Err(diagnostics) = run(br#"print(1 + "a");"#.to_vec()) else {
+        panic!("running succeeded");
+    };
+    let annotations = &diagnostics[0].annotations;
+    assert_eq!(
+        annotations
+            .labels
+            .iter()
+            .map(|label| label.message.as_str())
+            .collect::<Vec<_>>(),
+        ["left operand is `Number`", "right operand is `String`"]
+    );
+}
+
+#[test]
+fn warnings_come_with_the_output() {
+    let output = match compile(b"loop { draw.clear(0, 0, 0); }".to_vec()) {
+        Ok(output) => output,
+        Err(diagnostics) => panic!("compiling failed: {diagnostics:#?}"),
+    };
+    assert!(output.code.starts_with("draw clear"), "{}", output.code);
+    assert_eq!(output.warnings.len(), 1);
+    assert_eq!(output.warnings[0].severity, Severity::Warning);
+    assert_eq!(output.warnings[0].stage, Stage::Compile);
+}
+
+#[test]
+fn run_returns_the_flushed_text() {
+    assert_eq!(
+        run(b"print(1); printflush(message1); print(2); printflush(message1);".to_vec()).unwrap(),
+        "1\n2\n"
+    );
+}
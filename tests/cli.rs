@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs the binary with `args`, giving it `stdin` as standard input.
+fn cli(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mindustry-transpiler"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary runs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn compile_reads_standard_input() {
+    let output = cli(&["compile"], "var a = 1 + 2;");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "set a 3\n");
+
+    let output = cli(
+        &["compile", "-", "--target", "v8"],
+        "var a = 1; var b = a > 0 ? a : 2;",
+    );
+    assert!(stdout(&output).contains("select"), "{}", stdout(&output));
+}
+
+#[test]
+fn compile_writes_to_the_output_file() {
+    let path = std::env::temp_dir().join(format!("cli-test-{}.mlog", std::process::id()));
+    let output = cli(&["compile", "-o", path.to_str().unwrap()], "print(1);");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "print 1\nprintflush message1\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn run_check_tokens_and_ast() {
+    let output = cli(&["run"], "print(1 + 2); printflush(message1);");
+    assert_eq!(stdout(&output), "3\n");
+
+    let output = cli(&["check"], "var a = 1;");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+
+    let recursive = "fun f(n) { if n > 0 { return f(n - 1); } return 0; } f(3);";
+    assert_eq!(cli(&["check"], recursive).status.code(), Some(5));
+    let output = cli(
+        &["check", "--stack", "cell1:64", "--target", "v8"],
+        recursive,
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = cli(&["tokens"], "a;");
+    assert_eq!(
+        stdout(&output).lines().map(str::trim).collect::<Vec<_>>(),
+        ["1:1 Identifier 'a'", "1:2 Semicolon ';'", "1:3 EOF ''"]
+    );
+
+    let output = cli(&["ast"], "var a = 1 + 2;");
+    assert_eq!(stdout(&output), "(var a (+ 1 2))\n");
+}
+
+#[test]
+fn exit_codes_tell_failures_apart() {
+    assert_eq!(cli(&["frobnicate"], "").status.code(), Some(1));
+    assert_eq!(cli(&["run", "-o", "out"], "").status.code(), Some(1));
+    assert_eq!(cli(&["check", "-o", "out"], "").status.code(), Some(1));
+    assert_eq!(
+        cli(&["run", "--stack", "cell1:64"], "").status.code(),
+        Some(1)
+    );
+    assert_eq!(
+        cli(&["check", "--stack", "cell1:0"], "").status.code(),
+        Some(1)
+    );
+    assert_eq!(cli(&["compile"], "var a = 1 $ 2;").status.code(), Some(2));
+    assert_eq!(cli(&["compile"], "var a = ;").status.code(), Some(3));
+    assert_eq!(cli(&["run"], "print(1 + \"a\");").status.code(), Some(4));
+    assert_eq!(cli(&["check"], "var a = b;").status.code(), Some(5));
+}
+
+#[test]
+fn diagnostics_go_to_standard_error() {
+    let output = cli(&["check"], "var a = b;");
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("error: Undefined variable 'b'."),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn truncated_input_is_a_parse_error() {
+    for source in ["print(", "var x =", "x +", "a[", "i+", "@unit."] {
+        for command in ["check", "compile"] {
+            let output = cli(&[command], source);
+            assert_eq!(
+                output.status.code(),
+                Some(3),
+                "{command} {source:?}: {}",
+                stderr(&output)
+            );
+        }
+    }
+}
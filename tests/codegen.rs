@@ -0,0 +1,770 @@
+use mindustry_transpiler::{
+    codegen::{Compiler, StackConfig, Target},
+    compile, compile_with,
+};
+
+/// Compiles `source`, panicking with the diagnostics if it fails.
+fn mlog_with(source: &str, compiler: Compiler) -> String {
+    match compile_with(source.as_bytes().to_vec(), compiler) {
+        Ok(output) => output.code,
+        Err(diagnostics) => panic!("{source:?} failed to compile: {diagnostics:#?}"),
+    }
+}
+
+/// Compares the mlog for `source` with `expected`, ignoring the blank lines
+/// around it.
+fn assert_mlog(source: &str, expected: &str) {
+    assert_eq!(mlog_with(source, Compiler::new()), expected.trim());
+}
+
+/// The messages of the diagnostics compiling `source` fails with.
+fn errors(source: &str) -> Vec<String> {
+    match compile(source.as_bytes().to_vec()) {
+        Ok(output) => panic!("{source:?} compiled to:\n{}", output.code),
+        Err(diagnostics) => diagnostics.into_iter().map(|d| d.message).collect(),
+    }
+}
+
+#[test]
+fn arithmetic_goes_through_temporaries() {
+    assert_mlog(
+        "var a = 2; var b = -(a + 3) * a ** 2 // 4 % 3;",
+        "
+set a 2
+op add __t0 a 3
+op sub __t1 0 __t0
+op pow __t2 a 2
+op mul __t3 __t1 __t2
+op idiv __t4 __t3 4
+op mod __t5 __t4 3
+set b __t5
+",
+    );
+}
+
+#[test]
+fn bitwise_and_comparison_operators() {
+    assert_mlog(
+        "var a = 2; var c = a << 1 | a & 3 ^ ~a; var d = a >= c; var e = a === c;",
+        "
+set a 2
+op shl __t0 a 1
+op and __t1 a 3
+op not __t2 a
+op xor __t3 __t1 __t2
+op or __t4 __t0 __t3
+set c __t4
+op greaterThanEq __t5 a c
+set d __t5
+op strictEqual __t6 a c
+set e __t6
+",
+    );
+}
+
+#[test]
+fn unary_plus_adds_nothing() {
+    assert_mlog("var a = 1; var b = +a;", "set a 1\nset b a");
+}
+
+#[test]
+fn literals_are_written_as_mlog_constants() {
+    assert_mlog(
+        r#"var a = "hi"; var b = true; var c = null; var d = 1.5;"#,
+        r#"
+set a "hi"
+set b true
+set c null
+set d 1.5
+"#,
+    );
+}
+
+#[test]
+fn undefined_variables_are_rejected() {
+    assert_eq!(errors("var a = b + 1;"), ["Undefined variable 'b'."]);
+}
+
+#[test]
+fn operands_keep_their_value_when_a_later_operand_assigns_them() {
+    assert_mlog(
+        "var x = 1; var y = x + (x = 5); x += (x = 2);",
+        "
+set x 1
+set __t0 x
+set x 5
+op add __t1 __t0 x
+set y __t1
+set __t2 x
+set x 2
+op add x __t2 x
+",
+    );
+}
+
+#[test]
+fn arguments_are_copied_before_parameters_overwrite_them() {
+    let mlog = mlog_with(
+        "fun f(a, b) { if (a > b) { return f(b, a); } return a; } var r = f(2, 1);",
+        Compiler::new()
+            .with_stack(StackConfig {
+                block: "cell1".to_owned(),
+                size: 16,
+            })
+            .unwrap(),
+    );
+    let copy = mlog.find("set __t1 __f_a\n").expect(&mlog);
+    let call = mlog.find("set __f_a __f_b\nset __f_b __t1\n").expect(&mlog);
+    assert!(copy < call, "{mlog}");
+}
+
+#[test]
+fn if_else_jumps_to_resolved_labels() {
+    assert_mlog(
+        r#"var a = 1; if a > 0 { a = 2; } else { a = 3; } if a == 2 { print("two"); }"#,
+        r#"
+set a 1
+jump 4 lessThanEq a 0
+set a 2
+jump 5 always 0 0
+set a 3
+jump 7 notEqual a 2
+print "two"
+printflush message1
+"#,
+    );
+}
+
+#[test]
+fn loops_jump_back_to_their_start() {
+    assert_mlog(
+        "
+var s = 0;
+outer: for var i = 0; i < 3; i = i + 1 {
+    var j = 0;
+    while true {
+        j = j + 1;
+        if j > i { continue outer; }
+        if s > 5 { break outer; }
+        s = s + j;
+    }
+}
+",
+        "
+set s 0
+set i 0
+jump 16 greaterThanEq i 3
+set j 0
+op add __t0 j 1
+set j __t0
+jump 8 lessThanEq j i
+jump 13 always 0 0
+jump 10 lessThanEq s 5
+jump 16 always 0 0
+op add __t1 s j
+set s __t1
+jump 4 always 0 0
+op add __t2 i 1
+set i __t2
+jump 2 always 0 0
+",
+    );
+}
+
+#[test]
+fn functions_are_called_through_the_return_address() {
+    assert_mlog(
+        "fun add(a, b) { return a + b; } var c = add(1, 2);",
+        "
+set __add_a 1
+set __add_b 2
+op add __add_return @counter 1
+jump 7 always 0 0
+set __t0 __add_result
+set c __t0
+end
+op add __t1 __add_a __add_b
+set __add_result __t1
+set @counter __add_return
+set __add_result null
+set @counter __add_return
+",
+    );
+}
+
+#[test]
+fn functions_only_see_globals_declared_before_them() {
+    assert_eq!(
+        errors("var a = 1; fun f() { return a + b; } var b = 2; f();"),
+        ["Undefined variable 'b'."]
+    );
+}
+
+#[test]
+fn only_numbers_are_saved_on_the_stack() {
+    let source = "fun f(n) { var b = getlink(n); if n > 0 { f(n - 1); } print(b); } f(3);";
+    let stack = StackConfig {
+        block: "cell1".to_owned(),
+        size: 64,
+    };
+    let diagnostics = match compile_with(
+        source.as_bytes().to_vec(),
+        Compiler::new().with_stack(stack).unwrap(),
+    ) {
+        Ok(output) => panic!("{source:?} compiled to:\n{}", output.code),
+        Err(diagnostics) => diagnostics,
+    };
+    assert_eq!(
+        diagnostics[0].message,
+        "Local 'b' may not be a number, so it can't be saved on the call stack."
+    );
+}
+
+#[test]
+fn calls_save_their_frame_on_the_stack() {
+    let stack = StackConfig {
+        block: "cell1".to_owned(),
+        size: 8,
+    };
+    assert_eq!(
+        mlog_with(
+            "fun f(n) { if n > 0 { return f(n - 1) + n; } return 0; } var r = f(3);",
+            Compiler::new().with_stack(stack).unwrap(),
+        ),
+        r#"
+set __sp 0
+set __f_n 3
+op add __f_return @counter 1
+jump 7 always 0 0
+set __t0 __f_result
+set r __t0
+end
+jump 29 lessThanEq __f_n 0
+op sub __t1 __f_n 1
+jump 33 greaterThan __sp 5
+write __f_return cell1 __sp
+op add __sp __sp 1
+write __f_n cell1 __sp
+op add __sp __sp 1
+write __t1 cell1 __sp
+op add __sp __sp 1
+set __f_n __t1
+op add __f_return @counter 1
+jump 7 always 0 0
+op sub __sp __sp 1
+read __t1 cell1 __sp
+op sub __sp __sp 1
+read __f_n cell1 __sp
+op sub __sp __sp 1
+read __f_return cell1 __sp
+set __t2 __f_result
+op add __t3 __t2 __f_n
+set __f_result __t3
+set @counter __f_return
+set __f_result 0
+set @counter __f_return
+set __f_result null
+set @counter __f_return
+print "Stack overflow"
+stop
+"#
+        .trim()
+    );
+}
+
+#[test]
+fn the_stack_must_fit_in_a_memory_block() {
+    let stack = |block: &str, size| {
+        Compiler::new()
+            .with_stack(StackConfig {
+                block: block.to_owned(),
+                size,
+            })
+            .err()
+    };
+    assert_eq!(stack("bank1", 512), None);
+    assert_eq!(
+        stack("cell1", 1000).as_deref(),
+        Some("The call stack needs 1 to 64 slots of 'cell1' but got 1000.")
+    );
+    assert_eq!(
+        stack("cell1", 0).as_deref(),
+        Some("The call stack needs 1 to 64 slots of 'cell1' but got 0.")
+    );
+    assert_eq!(
+        stack("foo", 10).as_deref(),
+        Some("The call stack must be in a memory cell or bank, not 'foo'.")
+    );
+}
+
+#[test]
+fn constants_mlog_cannot_write_are_rejected() {
+    assert_eq!(
+        errors("print(1 / 0);"),
+        ["Constant expression evaluates to `inf`, which mlog can't represent."]
+    );
+    assert_eq!(
+        errors("print(sqrt(-1));"),
+        ["Constant expression evaluates to `NaN`, which mlog can't represent."]
+    );
+}
+
+#[test]
+fn dead_short_circuit_operands_are_not_folded() {
+    assert_mlog(
+        r#"var a = false and (1 + "a"); var b = true or (1 + "a"); var c = false ? 1 + "a" : 2;"#,
+        "
+set a false
+set b true
+set c 2
+",
+    );
+}
+
+#[test]
+fn math_functions_compile_to_op() {
+    assert_mlog(
+        "var a = 30; var b = sin(a) + max(a, 2); var c = sin(30);",
+        "
+set a 30
+op sin __t0 a 0
+op max __t1 a 2
+op add __t2 __t0 __t1
+set b __t2
+set c 0.5
+",
+    );
+}
+
+#[test]
+fn modulo_by_six_digits_is_not_a_colour() {
+    assert_mlog(
+        "var n = 5; var m = n%100000; var c = %ff0000;",
+        "
+set n 5
+op mod __t0 n 100000
+set m __t0
+set c %ff0000ff
+",
+    );
+}
+
+#[test]
+fn colours_are_packed_and_blended() {
+    assert_mlog(
+        "var x = 0.5; var a = rgb(1, x, 0); var b = rgba(x, 0, 0, 1); var c = %[red]; var d = blend(%[red], %0000ff, x);",
+        "
+set x 0.5
+packcolor __t0 1 x 0 1
+set a __t0
+packcolor __t1 x 0 0 1
+set b __t1
+set c %ff0000ff
+op max __t2 x 0
+op min __t2 __t2 1
+op mul __t3 __t2 -1
+op add __t3 __t3 1
+op mul __t4 __t2 0
+op add __t4 __t4 0
+op mul __t5 __t2 1
+op add __t5 __t5 0
+op mul __t6 __t2 0
+op add __t6 __t6 1
+packcolor __t7 __t3 __t4 __t5 __t6
+set d __t7
+",
+    );
+}
+
+#[test]
+fn colour_components_are_unpacked_on_v8() {
+    let source = "var c = %ff000080; var a = c.a;";
+    assert_eq!(
+        mlog_with(source, Compiler::new().with_target(Target::V8)),
+        "
+set c %ff000080
+unpackcolor __t0 __t1 __t2 __t3 c
+set a __t3
+"
+        .trim()
+    );
+    assert_eq!(
+        errors(source),
+        ["Colour components of non-constant values need 'unpackcolor'."]
+    );
+    assert_mlog("var a = %ff000080.a;", "set a 0.5019608");
+}
+
+#[test]
+fn math_constants_are_passed_through() {
+    assert_mlog(
+        "var a = @time-1; var b = @pi * 2; print(@e);",
+        "
+op sub __t0 @time 1
+set a __t0
+op mul __t1 @pi 2
+set b __t1
+print @e
+printflush message1
+",
+    );
+}
+
+#[test]
+fn and_or_jump_past_their_right_operand() {
+    assert_mlog(
+        r#"
+fun f() { return true; }
+var a = 1;
+if a > 0 and f() { print("x"); }
+var b = a < 0 or f();
+"#,
+        r#"
+set a 1
+jump 7 lessThanEq a 0
+op add __f_return @counter 1
+jump 17 always 0 0
+set __t0 __f_result
+jump 7 equal __t0 false
+print "x"
+op lessThan __t1 a 0
+set __t2 __t1
+jump 14 notEqual __t2 false
+op add __f_return @counter 1
+jump 17 always 0 0
+set __t3 __f_result
+set __t2 __t3
+set b __t2
+printflush message1
+end
+set __f_result true
+set @counter __f_return
+set __f_result null
+set @counter __f_return
+"#,
+    );
+}
+
+#[test]
+fn conditional_branches_must_agree_on_their_type() {
+    assert_eq!(
+        errors(r#"var c = true; var a = c ? 1 : "a";"#),
+        ["Conditional branch types `Number` and `String` are incompatible."]
+    );
+    assert_mlog(
+        "var c = true; var a = c ? 1 : null;",
+        "
+set c true
+jump 4 equal c false
+set __t0 1
+jump 5 always 0 0
+set __t0 null
+set a __t0
+",
+    );
+}
+
+#[test]
+fn conditionals_select_only_plain_operands() {
+    assert_eq!(
+        mlog_with(
+            "var a = 1; var b = a > 0 ? a : 2; var c = a > 0 ? a + 1 : 2;",
+            Compiler::new().with_target(Target::V8),
+        ),
+        "
+set a 1
+op greaterThan __t1 a 0
+select __t0 notEqual __t1 false a 2
+set b __t0
+jump 8 lessThanEq a 0
+op add __t3 a 1
+set __t2 __t3
+jump 9 always 0 0
+set __t2 2
+set c __t2
+"
+        .trim()
+    );
+}
+
+#[test]
+fn increments_update_in_place() {
+    assert_mlog(
+        "var i = 0; i++; i--; for var j = 0; j < 2; j++ { i += 2; } var k = i - -1;",
+        "
+set i 0
+op add i i 1
+op sub i i 1
+set j 0
+jump 8 greaterThanEq j 2
+op add i i 2
+op add j j 1
+jump 4 always 0 0
+op sub __t0 i -1
+set k __t0
+",
+    );
+}
+
+#[test]
+fn buildings_must_be_linked_before_use() {
+    assert_mlog(
+        "link cell1, message1; print(cell1.totalItems); printflush(message1);",
+        "
+sensor __t0 cell1 @totalItems
+print __t0
+printflush message1
+",
+    );
+    assert_eq!(
+        errors("printflush(message1);"),
+        ["Undefined variable 'message1'."]
+    );
+    assert_eq!(
+        errors("var a: [4] in cell1;"),
+        ["Undefined memory block 'cell1'."]
+    );
+}
+
+#[test]
+fn unit_controls_are_called_on_the_bound_unit() {
+    assert_mlog(
+        "
+fun move(x) { return x; }
+ubind(@poly);
+@unit.move(1, 2);
+var (kind, building, floor) = @unit.getBlock(3, 4);
+print(move(5));
+",
+        "
+ubind @poly
+ucontrol move 1 2 0 0 0
+ucontrol getBlock 3 4 __t0 __t1 __t2
+set kind __t0
+set building __t1
+set floor __t2
+set __move_x 5
+op add __move_return @counter 1
+jump 13 always 0 0
+set __t3 __move_result
+print __t3
+printflush message1
+end
+set __move_result __move_x
+set @counter __move_return
+set __move_result null
+set @counter __move_return
+",
+    );
+}
+
+#[test]
+fn ulocate_takes_the_arguments_of_its_kind() {
+    assert_mlog(
+        r#"
+var (a, ax, ay, ab) = ulocate("ore", @titanium);
+var (b, bx, by, bb) = ulocate("building", "turret", true);
+var (c, cx, cy, cb) = ulocate("spawn");
+"#,
+        "
+ulocate ore core true @titanium __t1 __t2 __t0 __t3
+set a __t0
+set ax __t1
+set ay __t2
+set ab __t3
+ulocate building turret true @copper __t5 __t6 __t4 __t7
+set b __t4
+set bx __t5
+set by __t6
+set bb __t7
+ulocate spawn core true @copper __t9 __t10 __t8 __t11
+set c __t8
+set cx __t9
+set cy __t10
+set cb __t11
+",
+    );
+    assert_eq!(
+        errors(r#"var (a, x, y, b) = ulocate("ore", "core", true);"#),
+        ["'ulocate' of kind 'ore' can't take 3 arguments."]
+    );
+}
+
+/// The messages of the warnings compiling `source` gives.
+fn warnings(source: &str) -> Vec<String> {
+    match compile(source.as_bytes().to_vec()) {
+        Ok(output) => output.warnings.into_iter().map(|d| d.message).collect(),
+        Err(diagnostics) => panic!("{source:?} failed to compile: {diagnostics:#?}"),
+    }
+}
+
+#[test]
+fn only_the_longer_branch_fills_the_draw_buffer() {
+    let line = "draw.line(0, 0, 1, 1);";
+    let source = format!(
+        "link display1; drawflush(display1); var a = 1; if a > 0 {{ {} }} else {{ {} }} {}",
+        line.repeat(200),
+        line.repeat(100),
+        line.repeat(100)
+    );
+    let mlog = mlog_with(&source, Compiler::new());
+    let lines: Vec<&str> = mlog.lines().collect();
+    let flushes: Vec<usize> = (0..lines.len())
+        .filter(|&i| lines[i] == "drawflush display1")
+        .collect();
+    let jump = lines.iter().rposition(|l| l.starts_with("jump")).unwrap();
+    // The else branch, then 56 more commands to fill the 256 after the
+    // then branch's 200.
+    assert_eq!(flushes, [0, jump + 157], "{mlog}");
+}
+
+#[test]
+fn loops_that_draw_must_flush() {
+    assert_eq!(
+        warnings("loop { draw.clear(0, 0, 0); for var i = 0; i < 9; i++ { draw.line(i, 0, i, 9); } }"),
+        ["Loop draws without 'drawflush', so the draw buffer fills up and later commands are dropped."]
+    );
+    assert!(warnings(
+        "link display1; loop { for var i = 0; i < 9; i++ { draw.line(i, 0, i, 9); } drawflush(display1); }"
+    )
+    .is_empty());
+}
+
+#[test]
+fn printed_text_is_flushed_to_message1() {
+    assert_mlog(
+        r#"var a = 1; print("a = {a}");"#,
+        r#"
+set a 1
+print "a = "
+print a
+printflush message1
+"#,
+    );
+    assert_mlog(
+        "link message2; print(1); printflush(message2);",
+        "print 1\nprintflush message2",
+    );
+}
+
+#[test]
+fn interpolations_format_only_into_a_clean_buffer() {
+    let source = r#"
+link message1;
+var x = 1;
+print("{{0}} then");
+print("v={x}");
+printflush(message1);
+print("w={x}");
+"#;
+    assert_mlog(
+        source,
+        r#"
+set x 1
+print "{0} then"
+print "v="
+print x
+printflush message1
+print "w="
+print x
+"#,
+    );
+    assert_eq!(
+        mlog_with(source, Compiler::new().with_target(Target::V8)),
+        r#"
+set x 1
+print "{0} then"
+print "v="
+print x
+printflush message1
+print "w={0}"
+format x
+"#
+        .trim()
+    );
+}
+
+#[test]
+fn loops_format_only_if_the_buffer_is_clean_again_at_the_end() {
+    let v8 = |source: &str| mlog_with(source, Compiler::new().with_target(Target::V8));
+    assert_eq!(
+        v8(r#"link message1; var x = 1; loop { print("a={x}"); printflush(message1); }"#),
+        r#"
+set x 1
+print "a={0}"
+format x
+printflush message1
+jump 1 always 0 0
+"#
+        .trim()
+    );
+    // The second time round, the buffer holds `s` from the first.
+    assert_eq!(
+        v8(r#"
+link message1;
+var x = 1;
+var s = "{{0}}";
+while x < 9 {
+    print("a={x}");
+    if x > 3 { print(s); continue; }
+    printflush(message1);
+    x += 1;
+}
+"#),
+        r#"
+set x 1
+set s "{0}"
+jump 11 greaterThanEq x 9
+print "a="
+print x
+jump 8 lessThanEq x 3
+print s
+jump 2 always 0 0
+printflush message1
+op add x x 1
+jump 2 always 0 0
+"#
+        .trim()
+    );
+}
+
+#[test]
+fn arrays_are_scoped_like_variables() {
+    assert_mlog(
+        "
+link cell1;
+var buf: [4] in cell1;
+{ var buf: [2] in cell1; buf[1] = 7; }
+buf[1] = 5;
+{ var buf = 3; print(buf); }
+",
+        "
+write 7 cell1 5
+write 5 cell1 1
+set buf 3
+print buf
+printflush message1
+",
+    );
+    assert_eq!(
+        errors("link cell1; { var buf: [2] in cell1; } buf[0] = 1;"),
+        ["Undefined array 'buf'."]
+    );
+}
+
+#[test]
+fn elements_are_read_once_by_compound_assignment() {
+    assert_mlog(
+        "link cell1; var buf: [4] in cell1; var i = 1; buf[i + 1] *= 3; buf[i]++;",
+        "
+set i 1
+op add __t0 i 1
+read __t1 cell1 __t0
+op mul __t2 __t1 3
+write __t2 cell1 __t0
+read __t3 cell1 i
+op add __t4 __t3 1
+write __t4 cell1 i
+",
+    );
+}
@@ -0,0 +1,204 @@
+use mindustry_transpiler::{interpreter::Interpreter, parse, run};
+
+/// What running `source` prints, panicking with the diagnostics if it fails.
+fn output(source: &str) -> String {
+    match run(source.as_bytes().to_vec()) {
+        Ok(output) => output,
+        Err(diagnostics) => panic!("{source:?} failed to run: {diagnostics:#?}"),
+    }
+}
+
+#[test]
+fn operands_are_evaluated_left_to_right() {
+    assert_eq!(
+        output("var x = 1; print(x + (x = 5)); x += (x = 2); print(x); printflush(message1);"),
+        "67\n"
+    );
+}
+
+#[test]
+fn if_else_runs_one_branch() {
+    assert_eq!(
+        output(
+            r#"
+var a = 1;
+if a > 1 { print("big"); } else if a == 1 { print("one"); } else { print("small"); }
+printflush(message1);
+"#
+        ),
+        "one\n"
+    );
+}
+
+#[test]
+fn loops_break_and_continue_by_label() {
+    assert_eq!(
+        output(
+            "
+var s = 0;
+outer: for var i = 0; i < 3; i = i + 1 {
+    var j = 0;
+    while true {
+        j = j + 1;
+        if j > i { continue outer; }
+        if s > 5 { break outer; }
+        s = s + j;
+    }
+}
+loop { s = s * 10; break; }
+print(s);
+printflush(message1);
+"
+        ),
+        "40\n"
+    );
+}
+
+#[test]
+fn functions_return_values() {
+    assert_eq!(
+        output(
+            "
+fun fib(n) { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); }
+print(fib(10));
+printflush(message1);
+"
+        ),
+        "55\n"
+    );
+}
+
+/// The messages of the diagnostics running `source` fails with.
+fn errors(source: &str) -> Vec<String> {
+    match run(source.as_bytes().to_vec()) {
+        Ok(output) => panic!("{source:?} ran and printed {output:?}"),
+        Err(diagnostics) => diagnostics.into_iter().map(|d| d.message).collect(),
+    }
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    assert_eq!(
+        errors("fun f(n) { return f(n + 1); } f(0);"),
+        ["Calls to 'f' nest too deeply."]
+    );
+}
+
+#[test]
+fn functions_only_see_globals_declared_before_them() {
+    assert_eq!(
+        errors("var a = 1; fun f() { return a + b; } var b = 2; f();"),
+        ["Undefined variable 'b'."]
+    );
+}
+
+/// What running `source` prints with `interpreter`.
+fn output_with(source: &str, mut interpreter: Interpreter) -> String {
+    let statements = parse(source.as_bytes().to_vec()).expect("source parses");
+    match interpreter.interpret(&statements) {
+        Ok(()) => interpreter.output,
+        Err(error) => panic!("{source:?} failed to run: {error:#?}"),
+    }
+}
+
+#[test]
+fn rand_is_seeded_per_run() {
+    let source = r#"print(rand(10), " ", rand(10), " ", rand(10)); printflush(message1);"#;
+    let seeded = output_with(source, Interpreter::new().with_seed(7));
+    assert_eq!(seeded, output_with(source, Interpreter::new().with_seed(7)));
+    for number in seeded.split_whitespace() {
+        let number: f32 = number.parse().unwrap();
+        assert!((0. ..10.).contains(&number), "{number}");
+    }
+    assert_ne!(output(source), output(source));
+}
+
+#[test]
+fn trig_takes_numbers_and_degrees() {
+    assert_eq!(
+        output(
+            r#"print(sin(30), " ", tan(45deg), " ", angleDiff(10, 350), " ", 7deg // 2deg); printflush(message1);"#
+        ),
+        "0.5 1 20deg 3deg\n"
+    );
+}
+
+#[test]
+fn and_or_skip_their_right_operand() {
+    assert_eq!(
+        output(
+            r#"
+fun f() { print("f"); return true; }
+var a = 1;
+if a < 0 and f() { print("x"); }
+print(a > 0 or f(), " ", a > 0 and f());
+printflush(message1);
+"#
+        ),
+        "ftrue true\n"
+    );
+}
+
+#[test]
+fn conditional_branches_must_have_compatible_types() {
+    assert_eq!(
+        errors("var a = 1; var b = true ? a : a < 2;"),
+        ["Conditional branch types `Number` and `Boolean` are incompatible."]
+    );
+    assert_eq!(
+        output("var a = 1; print(a > 0 ? a : null); printflush(message1);"),
+        "1\n"
+    );
+}
+
+#[test]
+fn increments_add_one() {
+    assert_eq!(
+        output("var i = 0; i++; i++; i--; i *= 10; print(i); printflush(message1);"),
+        "10\n"
+    );
+    assert_eq!(errors("j++;"), ["Undefined variable 'j'."]);
+}
+
+#[test]
+fn linked_buildings_only_exist_in_game() {
+    assert_eq!(
+        errors("link cell1; var a = cell1;"),
+        ["Building 'cell1' can only be evaluated in game."]
+    );
+    assert_eq!(errors("var a = cell1;"), ["Undefined variable 'cell1'."]);
+}
+
+#[test]
+fn braces_are_escaped_by_doubling() {
+    assert_eq!(
+        output(r#"var a = 2; print("}", "{{a}}", " {a} } {{", "{a + 1}"); printflush(message1);"#),
+        "}{a} 2 } {3\n"
+    );
+}
+
+#[test]
+fn unflushed_text_is_flushed_at_the_end() {
+    assert_eq!(output("print(1); print(2);"), "12\n");
+    assert_eq!(output("print(1); printflush(message1); print(2);"), "1\n");
+}
+
+#[test]
+fn arrays_are_scoped_and_updated_in_place() {
+    assert_eq!(
+        output(
+            "
+link cell1;
+var buf: [4] in cell1;
+buf[1] = 2;
+{ var buf: [2] in cell1; buf[1] = 9; }
+buf[1] += 3;
+buf[1]++;
+for var i = 0; i < 2; i++ { var seen: [1] in cell1; seen[0] += 1; print(seen[0]); }
+print(buf[1]);
+printflush(message1);
+"
+        ),
+        "126\n"
+    );
+}
@@ -0,0 +1,188 @@
+use mindustry_transpiler::{parse, stmt::StmtLike};
+
+/// Each statement of `source` in its S-expression form.
+fn ast(source: &str) -> Vec<String> {
+    match parse(source.as_bytes().to_vec()) {
+        Ok(statements) => statements.iter().map(|s| s.to_string()).collect(),
+        Err(diagnostics) => panic!("{source:?} failed to parse: {diagnostics:#?}"),
+    }
+}
+
+/// The messages of the diagnostics parsing `source` fails with.
+fn errors(source: &str) -> Vec<String> {
+    match parse(source.as_bytes().to_vec()) {
+        Ok(statements) => panic!("{source:?} parsed into {} statements", statements.len()),
+        Err(diagnostics) => diagnostics.into_iter().map(|d| d.message).collect(),
+    }
+}
+
+#[test]
+fn statements_declarations_and_blocks() {
+    assert_eq!(
+        ast("var a = 1; var b; { a + b; }"),
+        ["(var a 1)", "(var b)", "(block (expr (+ a b)))"]
+    );
+}
+
+#[test]
+fn statements_need_semicolons() {
+    assert_eq!(
+        errors("var a = 1"),
+        ["Expected ';' after variable declaration."]
+    );
+    assert_eq!(errors("1 + 2"), ["Expected ';' after expression."]);
+}
+
+#[test]
+fn blocks_need_closing_braces() {
+    assert_eq!(errors("{ var a = 1;"), ["Expected '}' after block."]);
+}
+
+#[test]
+fn broken_headers_take_their_block_with_them() {
+    assert_eq!(errors("while { }"), ["Expected expression."]);
+    assert_eq!(errors("fun f( { }"), ["Expected parameter name."]);
+    assert_eq!(
+        errors("if { { } } else { } var = 1;"),
+        ["Expected expression.", "Expected variable name."]
+    );
+}
+
+#[test]
+fn jumps_need_an_enclosing_loop() {
+    assert_eq!(
+        errors("outer: loop { break inner; }"),
+        ["Undeclared loop label 'inner'."]
+    );
+    assert_eq!(
+        errors("continue;"),
+        ["Cannot 'continue' outside of a loop."]
+    );
+}
+
+#[test]
+fn increments_are_compound_assignments() {
+    assert_eq!(
+        ast("i++; i--; i + +1; i - -1;"),
+        [
+            "(expr (++ i 1))",
+            "(expr (-- i 1))",
+            "(expr (+ i (+ 1)))",
+            "(expr (- i (- 1)))"
+        ]
+    );
+}
+
+#[test]
+fn links_name_buildings_at_the_top_level() {
+    assert_eq!(ast("link cell1, message1;"), ["(link cell1 message1)"]);
+    assert_eq!(
+        errors("link foo1;"),
+        ["'foo1' is not the name of a linked building."]
+    );
+    assert_eq!(
+        errors("{ link cell1; }"),
+        ["Links can only be declared at the top level."]
+    );
+}
+
+#[test]
+fn unit_controls_are_methods_of_the_unit() {
+    assert_eq!(
+        ast("fun move(x) { return x; } @unit.move(1, 2); move(3);"),
+        [
+            "(fun move (x) (return x))",
+            "(expr (@unit.move 1 2))",
+            "(expr (call move 3))"
+        ]
+    );
+    assert_eq!(errors("@unit.fly(1);"), ["Unknown unit control 'fly'."]);
+}
+
+#[test]
+fn draw_is_a_keyword() {
+    assert_eq!(ast("draw.stroke(2);"), ["(expr (draw.stroke 2))"]);
+    assert_eq!(errors("var draw = 1;"), ["Expected variable name."]);
+}
+
+#[test]
+fn interpolations_need_closing_braces() {
+    assert_eq!(
+        ast(r#"print("{a} }", "{{a}}");"#),
+        ["(expr (print (format a \" }\") {a}))"]
+    );
+    assert_eq!(errors(r#"print("{a");"#), ["Expected '}' in string."]);
+}
+
+#[test]
+fn elements_take_compound_assignments() {
+    assert_eq!(
+        ast("buf[i] += 1; buf[i]++; buf[i] - -1;"),
+        [
+            "(expr ([]+= buf i 1))",
+            "(expr ([]++ buf i 1))",
+            "(expr (- ([] buf i) (- 1)))"
+        ]
+    );
+}
+
+#[test]
+fn scan_errors_are_not_reported_again() {
+    assert_eq!(errors("var é = 1;"), ["Unexpected character."]);
+    assert_eq!(
+        errors("var a = 1 $ 2;\nprint(\"abc);"),
+        ["Unexpected character.", "Unterminated string."]
+    );
+    assert_eq!(
+        errors("var a = $;\nvar = 3;"),
+        ["Unexpected character.", "Expected variable name."]
+    );
+}
+
+/// The line, column and byte range of the first diagnostic parsing
+/// `source` fails with.
+fn error_span(source: &str) -> (usize, usize, usize, usize) {
+    match parse(source.as_bytes().to_vec()) {
+        Ok(statements) => panic!("{source:?} parsed into {} statements", statements.len()),
+        Err(diagnostics) => {
+            let span = diagnostics[0].span;
+            (span.ln, span.col, span.start, span.end)
+        }
+    }
+}
+
+#[test]
+fn nodes_span_their_source() {
+    let source = "var a = 1 +\n  2;\nprint(a);";
+    let statements = parse(source.as_bytes().to_vec()).unwrap();
+    let spans: Vec<_> = statements
+        .iter()
+        .map(|statement| {
+            let span = statement.span();
+            &source[span.start..span.end]
+        })
+        .collect();
+    assert_eq!(spans, ["var a = 1 +\n  2;", "print(a);"]);
+}
+
+#[test]
+fn errors_point_into_lines_and_strings() {
+    assert_eq!(error_span("var a = 1;\n  var = 2;"), (2, 7, 17, 18));
+    assert_eq!(error_span("print(\"ab {x y}\");"), (1, 14, 13, 14));
+}
+
+#[test]
+fn input_can_end_mid_expression() {
+    assert_eq!(errors("print("), ["Expected expression."]);
+    assert_eq!(errors("var x ="), ["Expected expression."]);
+    assert_eq!(errors("x"), ["Expected ';' after expression."]);
+}
+
+#[test]
+fn placeholders_need_an_expression() {
+    assert_eq!(
+        errors(r#"print("{}");"#),
+        ["Expected expression inside '{}'."]
+    );
+    assert_eq!(error_span("print(\"a\n { }\");"), (2, 2, 10, 13));
+}
@@ -0,0 +1,61 @@
+use mindustry_transpiler::{
+    error::{Diagnostic, Stage},
+    report::render,
+    token::Span,
+};
+
+/// The span of the last `text` in `source`, which is all on line 1.
+fn span(source: &str, text: &str) -> Span {
+    let start = source.rfind(text).unwrap();
+    Span {
+        file: 0,
+        start,
+        end: start + text.len(),
+        ln: 1,
+        col: start + 1,
+    }
+}
+
+#[test]
+fn labels_hang_below_the_line() {
+    let source = "var c = a + b;";
+    let diagnostic = Diagnostic::at(Stage::Evaluation, span(source, "+"), "Bad.".to_owned())
+        .with_label(span(source, "a"), "left".to_owned())
+        .with_label(span(source, "b"), "right".to_owned())
+        .with_help("fix it".to_owned());
+    assert_eq!(
+        render(&diagnostic, "x.mlt", source, false),
+        "\
+error: Bad.
+ --> x.mlt:1:11
+  |
+1 | var c = a + b;
+  |         - ^ - right
+  |         |
+  |         left
+  = help: fix it
+"
+    );
+}
+
+#[test]
+fn labels_can_share_a_column() {
+    let source = "var c = a + b;";
+    let diagnostic = Diagnostic::at(Stage::Evaluation, span(source, "+"), "Bad.".to_owned())
+        .with_label(span(source, "a"), "first".to_owned())
+        .with_label(span(source, "a"), "second".to_owned())
+        .with_label(span(source, "b"), "right".to_owned());
+    assert_eq!(
+        render(&diagnostic, "x.mlt", source, false),
+        "\
+error: Bad.
+ --> x.mlt:1:11
+  |
+1 | var c = a + b;
+  |         - ^ - right
+  |         |
+  |         second
+  |         first
+"
+    );
+}
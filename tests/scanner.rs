@@ -0,0 +1,111 @@
+use mindustry_transpiler::tokens;
+
+/// The type and lexeme of each token in `source`, leaving out the final EOF.
+fn scan(source: &str) -> Vec<String> {
+    match tokens(source.as_bytes().to_vec()) {
+        Ok(tokens) => tokens
+            .iter()
+            .filter(|token| !token.lexeme.is_empty())
+            .map(|token| format!("{:?} {}", token.ttype, token.lexeme))
+            .collect(),
+        Err(diagnostics) => panic!("{source:?} failed to scan: {diagnostics:#?}"),
+    }
+}
+
+#[test]
+fn percent_after_an_operand_is_modulo() {
+    assert_eq!(
+        scan("n%100000"),
+        ["Identifier n", "Percent %", "Number 100000"]
+    );
+    assert_eq!(
+        scan("(n)%ff0000 % 2"),
+        [
+            "LParen (",
+            "Identifier n",
+            "RParen )",
+            "Percent %",
+            "Identifier ff0000",
+            "Percent %",
+            "Number 2"
+        ]
+    );
+}
+
+#[test]
+fn percent_before_hex_digits_is_a_colour() {
+    assert_eq!(
+        scan("c = %ff0000 + %[red];"),
+        [
+            "Identifier c",
+            "Equals =",
+            "Colour %ff0000",
+            "Plus +",
+            "Colour %[red]",
+            "Semicolon ;"
+        ]
+    );
+}
+
+#[test]
+fn at_names_stop_at_the_longest_built_in() {
+    assert_eq!(
+        scan("@time-1 @phase-fabric-@pi"),
+        [
+            "AtIdentifier @time",
+            "Minus -",
+            "Number 1",
+            "AtIdentifier @phase-fabric",
+            "Minus -",
+            "AtIdentifier @pi"
+        ]
+    );
+}
+
+#[test]
+fn only_strings_with_an_open_brace_interpolate() {
+    assert_eq!(
+        scan(r#""}" "{{a}}" "{a}" "{{{a}""#),
+        [
+            "String }",
+            "String {{a}}",
+            "Interpolation {a}",
+            "Interpolation {{{a}"
+        ]
+    );
+}
+
+/// Each token of `source` with its line, column and byte range.
+fn spans(source: &str) -> Vec<(String, usize, usize, usize, usize)> {
+    match tokens(source.as_bytes().to_vec()) {
+        Ok(tokens) => tokens
+            .iter()
+            .map(|token| {
+                let span = token.span;
+                (
+                    token.lexeme.to_owned(),
+                    span.ln,
+                    span.col,
+                    span.start,
+                    span.end,
+                )
+            })
+            .collect(),
+        Err(diagnostics) => panic!("{source:?} failed to scan: {diagnostics:#?}"),
+    }
+}
+
+#[test]
+fn columns_count_characters_from_the_last_newline() {
+    assert_eq!(
+        spans("a\n  \"é\" c\n\"x\ny\" d"),
+        [
+            ("a".to_owned(), 1, 1, 0, 1),
+            ("é".to_owned(), 2, 3, 4, 8),
+            ("c".to_owned(), 2, 7, 9, 10),
+            ("x\ny".to_owned(), 3, 1, 11, 16),
+            ("d".to_owned(), 4, 4, 17, 18),
+            ("".to_owned(), 4, 5, 18, 18),
+        ]
+    );
+}